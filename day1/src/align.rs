@@ -0,0 +1,124 @@
+// Fewer depth changes than this correlate perfectly by chance far too often
+pub const MIN_OVERLAP: usize = 4;
+
+#[derive(Debug)]
+pub struct Alignment {
+    pub offset: isize,
+    pub overlap: usize,
+    // Lower end of the 95% interval for the correlation, so that a short
+    // overlap has to correlate better than a long one to win
+    pub confidence: f64,
+    // Mean of `sweep - reference` over the overlap
+    pub bias: f64,
+}
+
+fn deltas(depths: &[i32]) -> Vec<f64> {
+    depths.windows(2).map(|w| (w[1] - w[0]) as f64).collect()
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+
+    let mut cov   = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        cov   += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+
+    Some(cov / (var_a * var_b).sqrt())
+}
+
+// Fisher's z-transform gives the correlation of `n` pairs a standard error
+// of 1 / sqrt(n - 3)
+fn confidence(r: f64, n: usize) -> f64 {
+    (r.atanh() - 1.96 / ((n - 3) as f64).sqrt()).tanh()
+}
+
+// Finds the offset at which `sweep[j]` lines up with `reference[j + offset]`
+// by correlating the depth changes of both sweeps, which does not care
+// about a constant depth bias between the two recorders.
+pub fn align(reference: &[i32], sweep: &[i32], min_overlap: usize) -> Option<Alignment> {
+    let da = deltas(reference);
+    let db = deltas(sweep);
+
+    let min_overlap = min_overlap.max(MIN_OVERLAP);
+    let mut best: Option<Alignment> = None;
+
+    for offset in -(db.len() as isize) + 1..da.len() as isize {
+        let start = (-offset).max(0) as usize;
+        let end   = db.len().min((da.len() as isize - offset) as usize);
+
+        if end < start + min_overlap {
+            continue;
+        }
+
+        let a = &da[(start as isize + offset) as usize..(end as isize + offset) as usize];
+        let b = &db[start..end];
+
+        if let Some(score) = correlation(a, b).map(|r| confidence(r, end - start)) {
+            let better = match &best {
+                Some(best) => score > best.confidence || (score == best.confidence && end - start > best.overlap),
+                None => true,
+            };
+
+            if better {
+                best = Some(Alignment { offset, overlap: end - start, confidence: score, bias: 0.0 });
+            }
+        }
+    }
+
+    best.map(|best| Alignment { bias: bias(reference, sweep, best.offset), ..best })
+}
+
+fn bias(reference: &[i32], sweep: &[i32], offset: isize) -> f64 {
+    let differences: Vec<f64> = sweep
+        .iter()
+        .enumerate()
+        .filter_map(|(j, &depth)| {
+            let i = j as isize + offset;
+            (i >= 0 && (i as usize) < reference.len()).then(|| (depth - reference[i as usize]) as f64)
+        })
+        .collect();
+
+    differences.iter().sum::<f64>() / differences.len().max(1) as f64
+}
+
+// Averages the aligned sweeps after taking out each one's bias, so that the
+// overlaps do not start and end with an artificial step
+pub fn merge(sweeps: &[Vec<i32>], offsets: &[isize], biases: &[f64]) -> Vec<i32> {
+    let start = offsets.iter().copied().min().unwrap_or(0).min(0);
+    let end = sweeps
+        .iter()
+        .zip(offsets.iter())
+        .map(|(sweep, &offset)| sweep.len() as isize + offset)
+        .max()
+        .unwrap_or(0);
+
+    let len = (end - start).max(0) as usize;
+    let mut sums   = vec![0.0; len];
+    let mut counts = vec![0i64; len];
+
+    for ((sweep, &offset), &bias) in sweeps.iter().zip(offsets.iter()).zip(biases.iter()) {
+        for (j, &depth) in sweep.iter().enumerate() {
+            let i = (j as isize + offset - start) as usize;
+            sums[i]   += depth as f64 - bias;
+            counts[i] += 1;
+        }
+    }
+
+    sums.iter()
+        .zip(counts.iter())
+        .filter(|(_, &c)| c > 0)
+        .map(|(&s, &c)| (s / c as f64).round() as i32)
+        .collect()
+}
@@ -0,0 +1,147 @@
+pub enum Repair {
+    Interpolate,
+    Drop,
+}
+
+pub struct GlitchConfig {
+    pub max_jump: Option<i32>,
+    pub median_window: usize,
+    pub median_tolerance: Option<i32>,
+}
+
+impl GlitchConfig {
+    pub fn enabled(&self) -> bool {
+        self.max_jump.is_some() || self.median_tolerance.is_some()
+    }
+}
+
+// Readings that jump away and come back within this many samples are a
+// dropout; a longer excursion is taken to be a real feature of the floor
+const MAX_DROPOUT: usize = 3;
+
+// Flags dropouts: runs of readings that step more than `max_jump` away from
+// the reading before them and then step back to within `max_jump` of it. A
+// reading that never comes back is a genuine step in the profile. At either
+// end of the sweep a single reading is a spike when it jumps away from the
+// two nearest readings.
+fn dropouts(depths: &[i32], max_jump: i32) -> Vec<bool> {
+    let n = depths.len();
+    let jumps = |a: i32, b: i32| (a - b).abs() > max_jump;
+
+    let mut spikes = vec![false; n];
+
+    if n < 2 {
+        return spikes;
+    }
+
+    for i in [0, n - 1] {
+        let neighbours = if i == 0 { &depths[1..n.min(3)] } else { &depths[n.saturating_sub(3)..i] };
+        spikes[i] = neighbours.iter().all(|&d| jumps(depths[i], d));
+    }
+
+    let mut i = 1;
+    while i + 1 < n {
+        let anchor = depths[i - 1];
+        let back = depths[i..(i + MAX_DROPOUT + 1).min(n)].iter().position(|&d| !jumps(d, anchor));
+
+        match back {
+            Some(len) if len > 0 => {
+                spikes[i..i + len].fill(true);
+                i += len;
+            },
+            _ => i += 1,
+        }
+    }
+
+    spikes
+}
+
+pub fn detect(depths: &[i32], config: &GlitchConfig) -> Vec<usize> {
+    let n = depths.len();
+    let half = config.median_window / 2;
+
+    let spikes = match config.max_jump {
+        Some(max_jump) => dropouts(depths, max_jump),
+        None => vec![false; n],
+    };
+
+    let mut flagged = Vec::new();
+
+    for i in 0..n {
+        let outlier = config.median_tolerance.is_some_and(|tolerance| {
+            let lo = i.saturating_sub(half);
+            let hi = (i + half + 1).min(n);
+            (depths[i] - median(&depths[lo..hi])).abs() > tolerance
+        });
+
+        if spikes[i] || outlier {
+            flagged.push(i);
+        }
+    }
+
+    flagged
+}
+
+fn median(values: &[i32]) -> i32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    }
+}
+
+pub fn repair(depths: &[i32], flagged: &[usize], mode: &Repair) -> Vec<i32> {
+    let mut bad = vec![false; depths.len()];
+    for &i in flagged.iter() {
+        bad[i] = true;
+    }
+
+    match mode {
+        Repair::Drop => {
+            depths
+                .iter()
+                .zip(bad.iter())
+                .filter(|(_, &b)| !b)
+                .map(|(&d, _)| d)
+                .collect()
+        },
+        Repair::Interpolate => {
+            let mut repaired = depths.to_vec();
+
+            let mut i = 0;
+            while i < depths.len() {
+                if !bad[i] {
+                    i += 1;
+                    continue;
+                }
+
+                // Find the extent of this run of flagged readings
+                let start = i;
+                while i < depths.len() && bad[i] {
+                    i += 1;
+                }
+
+                let before = start.checked_sub(1).map(|j| depths[j]);
+                let after  = depths.get(i).copied();
+
+                for (k, j) in (start..i).enumerate() {
+                    repaired[j] = match (before, after) {
+                        (Some(a), Some(b)) => {
+                            let t = (k + 1) as f64 / (i - start + 1) as f64;
+                            (a as f64 + t * (b - a) as f64).round() as i32
+                        },
+                        (Some(a), None) => a,
+                        (None, Some(b)) => b,
+                        (None, None) => depths[j],
+                    };
+                }
+            }
+
+            repaired
+        },
+    }
+}
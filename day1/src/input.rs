@@ -0,0 +1,99 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, prelude::*};
+use std::num::ParseIntError;
+
+pub enum BadLinePolicy {
+    Fail,
+    Skip,
+    Collect,
+}
+
+#[derive(Debug)]
+pub struct BadLine {
+    pub source: String,
+    pub line: usize,
+    pub text: String,
+    pub error: ParseIntError,
+}
+
+impl fmt::Display for BadLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: cannot read {:?} as a depth ({})", self.source, self.line, self.text, self.error)
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    BadLine(BadLine),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io(e) => write!(f, "{}", e),
+            InputError::BadLine(bad) => write!(f, "{}", bad),
+        }
+    }
+}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        InputError::Io(e)
+    }
+}
+
+pub fn open_input(fname: &str) -> io::Result<Box<dyn BufRead>> {
+    if fname == "-" {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(fname)?)))
+    }
+}
+
+pub fn read_input(fname: &str, policy: &BadLinePolicy) -> Result<Vec<i32>, InputError> {
+    let mut depths = Vec::new();
+    for_each_depth(fname, policy, |depth| depths.push(depth))?;
+    Ok(depths)
+}
+
+pub fn for_each_depth(fname: &str, policy: &BadLinePolicy, f: impl FnMut(i32)) -> Result<(), InputError> {
+    let source = if fname == "-" { "<stdin>" } else { fname };
+    let bad_lines = parse_depths(open_input(fname)?, source, policy, f)?;
+
+    if !bad_lines.is_empty() {
+        eprintln!("Ignored {} bad line(s) in {}:", bad_lines.len(), source);
+        for bad in bad_lines.iter() {
+            eprintln!("  {}", bad);
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the bad lines gathered under `BadLinePolicy::Collect`
+pub fn parse_depths(reader: impl BufRead, source: &str, policy: &BadLinePolicy, mut f: impl FnMut(i32))
+    -> Result<Vec<BadLine>, InputError>
+{
+    let mut bad_lines = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let text = line?;
+
+        match text.parse() {
+            Ok(depth) => f(depth),
+            Err(error) => {
+                let bad = BadLine { source: source.to_string(), line: i + 1, text, error };
+
+                match policy {
+                    BadLinePolicy::Fail => return Err(InputError::BadLine(bad)),
+                    BadLinePolicy::Skip => eprintln!("Warning: skipping {}", bad),
+                    BadLinePolicy::Collect => bad_lines.push(bad),
+                }
+            },
+        }
+    }
+
+    Ok(bad_lines)
+}
@@ -1,65 +1,311 @@
+#[cfg(test)]
+mod tests;
+mod align;
+mod glitch;
+mod input;
+mod report;
+mod stream;
+mod window;
+
 use std::env;
-use std::io::{self, BufReader, prelude::*};
-use std::fs::File;
+use std::fmt;
+use std::process;
 
-fn read_input(fname: &str) -> io::Result<Vec<i32>> {
-    let f = File::open(fname)?;
-    let reader = BufReader::new(f);
+use crate::glitch::{GlitchConfig, Repair};
+use crate::input::{BadLinePolicy, InputError, for_each_depth, read_input};
+use crate::report::{DepthReport, ReportFormat};
+use crate::stream::DepthCounter;
+use crate::window::{Query, WindowQueries};
 
-    let mut depths = Vec::new();
+#[derive(Debug, PartialEq)]
+enum WindowError {
+    ZeroSize,
+    TooShort { window: usize, len: usize },
+}
 
-    for line in reader.lines() {
-        depths.push(line?.parse().unwrap());
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowError::ZeroSize => write!(f, "window size must be at least 1"),
+            WindowError::TooShort { window, len } => {
+                write!(f, "window of {} readings does not fit into {} readings", window, len)
+            },
+        }
     }
+}
 
-    Ok(depths)
+struct Options {
+    fname: String,
+    window: usize,
+    stream: bool,
+    every: Option<usize>,
+    report: Option<ReportFormat>,
+    bin_width: i32,
+    glitch: GlitchConfig,
+    repair: Repair,
+    bad_lines: BadLinePolicy,
+    merge: Vec<String>,
+    min_overlap: usize,
+    queries: Vec<Query>,
 }
 
-fn count_depth_increases(depths: &[i32]) -> usize {
-    let mut count = 0;
-    let mut prev  = depths[0];
+fn usage() -> ! {
+    eprintln!("Usage: day1 <input> [--window N] [--stream [--every N]]");
+    eprintln!("                    [--report text|json [--bin-width N]]");
+    eprintln!("                    [--max-jump N] [--median-tolerance N [--median-window N]]");
+    eprintln!("                    [--repair interpolate|drop]");
+    eprintln!("                    [--bad-lines fail|skip|collect]");
+    eprintln!("                    [--merge <other input>]... [--min-overlap N]");
+    eprintln!("                    [--query sum|mean|median|max|min:SIZE[:inc|dec|rise=T|rise%=P]]...");
+    eprintln!("       Use '-' as input to read from stdin");
+    process::exit(1);
+}
+
+fn fail(e: impl fmt::Display) -> ! {
+    eprintln!("Error: {}", e);
+    process::exit(1);
+}
 
-    for &depth in depths.iter().skip(1) {
-        if depth > prev {
-            count += 1;
+fn next_number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+    match args.next().and_then(|n| n.parse().ok()) {
+        Some(n) => n,
+        None => usage(),
+    }
+}
+
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+
+    let mut fname  = None;
+    let mut window = 3;
+    let mut stream = false;
+    let mut every  = None;
+    let mut report = None;
+    let mut bin_width = 10;
+    let mut glitch = GlitchConfig { max_jump: None, median_window: 5, median_tolerance: None };
+    let mut repair = Repair::Interpolate;
+    let mut bad_lines = BadLinePolicy::Fail;
+    let mut merge = Vec::new();
+    let mut min_overlap = 10;
+    let mut queries = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => window = next_number(&mut args),
+            "--stream" => stream = true,
+            "--every" => every = Some(next_number(&mut args)).filter(|&n| n > 0),
+            "--report" => {
+                report = match args.next().as_deref() {
+                    Some("text") => Some(ReportFormat::Text),
+                    Some("json") => Some(ReportFormat::Json),
+                    _ => usage(),
+                };
+            },
+            "--bin-width" => bin_width = next_number(&mut args),
+            "--max-jump" => glitch.max_jump = Some(next_number(&mut args)),
+            "--median-window" => glitch.median_window = next_number(&mut args),
+            "--median-tolerance" => glitch.median_tolerance = Some(next_number(&mut args)),
+            "--repair" => {
+                repair = match args.next().as_deref() {
+                    Some("interpolate") => Repair::Interpolate,
+                    Some("drop") => Repair::Drop,
+                    _ => usage(),
+                };
+            },
+            "--bad-lines" => {
+                bad_lines = match args.next().as_deref() {
+                    Some("fail") => BadLinePolicy::Fail,
+                    Some("skip") => BadLinePolicy::Skip,
+                    Some("collect") => BadLinePolicy::Collect,
+                    _ => usage(),
+                };
+            },
+            "--merge" => merge.push(args.next().unwrap_or_else(|| usage())),
+            "--min-overlap" => {
+                min_overlap = next_number(&mut args);
+                if min_overlap < align::MIN_OVERLAP {
+                    fail(format!("--min-overlap must be at least {}", align::MIN_OVERLAP));
+                }
+            },
+            "--query" => {
+                match args.next().unwrap_or_else(|| usage()).parse() {
+                    Ok(query) => queries.push(query),
+                    Err(e) => fail(e),
+                }
+            },
+            _ if fname.is_none() => fname = Some(arg),
+            _ => usage(),
         }
-        prev = depth;
     }
 
-    count
+    if stream && (report.is_some() || glitch.enabled() || !merge.is_empty()) || bin_width < 1 {
+        usage();
+    }
+
+    match fname {
+        Some(fname) => Options {
+            fname, window, stream, every, report, bin_width, glitch, repair, bad_lines, merge, min_overlap,
+            queries,
+        },
+        None => usage(),
+    }
+}
+
+fn count_depth_increases(depths: &[i32]) -> usize {
+    depths.windows(2).filter(|w| w[1] > w[0]).count()
 }
 
-fn count_sliding_increases(depths: &[i32]) -> usize {
-    let mut count = 0;
-    let mut window: [i32; 3] = [depths[0] + depths[1] + depths[2],
-                                            depths[1] + depths[2],
-                                                        depths[2]];
+fn count_sliding_increases(depths: &[i32], size: usize) -> Result<usize, WindowError> {
+    if size == 0 {
+        return Err(WindowError::ZeroSize);
+    }
+
+    if depths.len() < size {
+        return Err(WindowError::TooShort { window: size, len: depths.len() });
+    }
+
+    // Two neighbouring windows share all but their outermost readings, so
+    // comparing the sums boils down to comparing those two readings.
+    let count = depths
+        .windows(size + 1)
+        .filter(|w| w[size] > w[0])
+        .count();
+
+    Ok(count)
+}
 
-    for (i, &depth) in depths.iter().enumerate().skip(3) {
-        let m = i % 3;
-        let n = (i + 1) % 3;
-        let k = (i + 2) % 3;
+fn stream_depths(options: &Options) -> Result<(DepthCounter, WindowQueries), InputError> {
+    let mut counter = DepthCounter::new(options.window).unwrap_or_else(|e| fail(e));
+    let mut queries = WindowQueries::new(&options.queries);
 
-        window[n] += depth;
-        window[k] += depth;
+    for_each_depth(&options.fname, &options.bad_lines, |depth| {
+        counter.push(depth);
+        queries.push(depth);
 
-        if window[n] > window[m] {
-            count += 1;
+        if let Some(every) = options.every {
+            if counter.readings().is_multiple_of(every) {
+                println!("After {} readings: depth increased {} times, sliding window sum increased {} times",
+                         counter.readings(), counter.depth_increases(),
+                         counter.sliding_increases().unwrap_or(0));
+            }
         }
+    })?;
+
+    Ok((counter, queries))
+}
 
-        window[m] = depth;
+fn print_queries(queries: &WindowQueries) {
+    for (query, result) in queries.results() {
+        match result {
+            Ok(count) => println!("{} {} times", query, count),
+            Err(e) => println!("{}: {}", query, e),
+        }
     }
+}
+
+fn print_glitch_comparison(depths: &[i32], options: &Options) {
+    let flagged  = glitch::detect(depths, &options.glitch);
+    let repaired = glitch::repair(depths, &flagged, &options.repair);
+
+    println!("Flagged {} of {} readings: {:?}", flagged.len(), depths.len(), flagged);
+
+    let sliding = |series: &[i32]| count_sliding_increases(series, options.window).ok();
+    let show = |n: Option<usize>| n.map_or("n/a".to_string(), |n| n.to_string());
 
-    count
+    println!("{:<30} {:>8} {:>8}", "", "raw", "repaired");
+    println!("{:<30} {:>8} {:>8}", "Depth increases",
+             count_depth_increases(depths), count_depth_increases(&repaired));
+    println!("{:<30} {:>8} {:>8}", format!("Sliding window ({}) increases", options.window),
+             show(sliding(depths)), show(sliding(&repaired)));
+}
+
+fn merge_sweeps(reference: Vec<i32>, options: &Options) -> Vec<i32> {
+    let mut sweeps  = vec![reference];
+    let mut offsets = vec![0];
+    let mut biases  = vec![0.0];
+
+    for fname in options.merge.iter() {
+        let sweep = read_input(fname, &options.bad_lines).unwrap_or_else(|e| fail(e));
+
+        match align::align(&sweeps[0], &sweep, options.min_overlap) {
+            Some(alignment) => {
+                println!("{}: offset {}, overlap {} readings, confidence {:.3}, depth bias {:+.2}",
+                         fname, alignment.offset, alignment.overlap, alignment.confidence, alignment.bias);
+                offsets.push(alignment.offset);
+                biases.push(alignment.bias);
+                sweeps.push(sweep);
+            },
+            None => fail(format!("{}: could not be aligned with {}", fname, options.fname)),
+        }
+    }
+
+    let merged = align::merge(&sweeps, &offsets, &biases);
+    println!("Merged {} sweeps into {} readings", sweeps.len(), merged.len());
+
+    merged
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let depths = read_input(&args[1]).unwrap();
+    let options = parse_args();
+
+    if options.stream {
+        let (counter, queries) = stream_depths(&options).unwrap_or_else(|e| fail(e));
+
+        println!("Depth increased {} times", counter.depth_increases());
+
+        match counter.sliding_increases() {
+            Ok(result2) => println!("Sliding window sum increased {} times", result2),
+            Err(e) => fail(e),
+        }
+
+        print_queries(&queries);
+        return;
+    }
+
+    let mut depths = read_input(&options.fname, &options.bad_lines).unwrap_or_else(|e| fail(e));
+
+    if !options.merge.is_empty() {
+        depths = merge_sweeps(depths, &options);
+    }
+
+    // Every line may have been skipped as bad, or the file may be empty
+    if depths.is_empty() {
+        fail(format_args!("{}: no depth readings", options.fname));
+    }
+
+    if let Some(format) = &options.report {
+        let report = match DepthReport::new(&depths, options.window, options.bin_width) {
+            Some(report) => report,
+            None => fail("no depth readings to report on"),
+        };
+
+        match format {
+            ReportFormat::Text => print!("{}", report),
+            ReportFormat::Json => println!("{}", report.to_json()),
+        }
+
+        return;
+    }
+
+    if options.glitch.enabled() {
+        print_glitch_comparison(&depths, &options);
+        return;
+    }
 
     let result1 = count_depth_increases(&depths);
     println!("Depth increased {} times", result1);
 
-    let result2 = count_sliding_increases(&depths);
-    println!("Sliding window sum increased {} times", result2);
+    match count_sliding_increases(&depths, options.window) {
+        Ok(result2) => println!("Sliding window sum increased {} times", result2),
+        Err(e) => fail(e),
+    }
+
+    if !options.queries.is_empty() {
+        let mut queries = WindowQueries::new(&options.queries);
+        for &depth in depths.iter() {
+            queries.push(depth);
+        }
+
+        print_queries(&queries);
+    }
 }
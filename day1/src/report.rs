@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{count_depth_increases, count_sliding_increases};
+
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+const PERCENTILES: [usize; 5] = [10, 25, 50, 75, 90];
+
+#[derive(Debug, PartialEq)]
+pub struct Run {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Run {
+    pub fn len(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Debug)]
+pub struct DepthReport {
+    pub readings: usize,
+    pub depth_increases: usize,
+    pub sliding_increases: Option<usize>,
+    pub window: usize,
+    pub longest_increase: Run,
+    pub longest_decrease: Run,
+    pub local_minima: Vec<usize>,
+    pub local_maxima: Vec<usize>,
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+    pub percentiles: Vec<(usize, i32)>,
+    pub bin_width: i32,
+    pub delta_histogram: BTreeMap<i32, usize>,
+}
+
+impl DepthReport {
+    pub fn new(depths: &[i32], window: usize, bin_width: i32) -> Option<Self> {
+        if depths.is_empty() || bin_width < 1 {
+            return None;
+        }
+
+        let mut sorted = depths.to_vec();
+        sorted.sort_unstable();
+
+        // Nearest-rank percentiles
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|&p| {
+                let rank = (p * sorted.len()).div_ceil(100).max(1);
+                (p, sorted[rank - 1])
+            })
+            .collect();
+
+        let mut delta_histogram = BTreeMap::new();
+        for w in depths.windows(2) {
+            let bin = (w[1] - w[0]).div_euclid(bin_width) * bin_width;
+            *delta_histogram.entry(bin).or_insert(0) += 1;
+        }
+
+        let sum: i64 = depths.iter().map(|&d| d as i64).sum();
+
+        Some(Self {
+            readings: depths.len(),
+            depth_increases: count_depth_increases(depths),
+            sliding_increases: count_sliding_increases(depths, window).ok(),
+            window,
+            longest_increase: longest_run(depths, |a, b| b > a),
+            longest_decrease: longest_run(depths, |a, b| b < a),
+            local_minima: local_extrema(depths, |d, n| d < n),
+            local_maxima: local_extrema(depths, |d, n| d > n),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sum as f64 / depths.len() as f64,
+            percentiles,
+            bin_width,
+            delta_histogram,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let run = |r: &Run| format!("{{\"start\": {}, \"end\": {}, \"length\": {}}}", r.start, r.end, r.len());
+        let list = |v: &[usize]| v.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ");
+
+        let sliding = match self.sliding_increases {
+            Some(n) => n.to_string(),
+            None => "null".to_string(),
+        };
+
+        let percentiles = self.percentiles
+            .iter()
+            .map(|(p, d)| format!("\"p{}\": {}", p, d))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let histogram = self.delta_histogram
+            .iter()
+            .map(|(bin, count)| format!("{{\"from\": {}, \"to\": {}, \"count\": {}}}", bin, bin + self.bin_width - 1, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut json = String::from("{\n");
+        json += &format!("  \"readings\": {},\n", self.readings);
+        json += &format!("  \"depth_increases\": {},\n", self.depth_increases);
+        json += &format!("  \"window\": {},\n", self.window);
+        json += &format!("  \"sliding_increases\": {},\n", sliding);
+        json += &format!("  \"longest_increase\": {},\n", run(&self.longest_increase));
+        json += &format!("  \"longest_decrease\": {},\n", run(&self.longest_decrease));
+        json += &format!("  \"local_minima\": [{}],\n", list(&self.local_minima));
+        json += &format!("  \"local_maxima\": [{}],\n", list(&self.local_maxima));
+        json += &format!("  \"min\": {},\n", self.min);
+        json += &format!("  \"max\": {},\n", self.max);
+        json += &format!("  \"mean\": {:.3},\n", self.mean);
+        json += &format!("  \"percentiles\": {{{}}},\n", percentiles);
+        json += &format!("  \"delta_histogram\": [{}]\n", histogram);
+        json += "}";
+
+        json
+    }
+}
+
+fn longest_run(depths: &[i32], step: impl Fn(i32, i32) -> bool) -> Run {
+    let mut best  = Run { start: 0, end: 0 };
+    let mut start = 0;
+
+    for i in 1..depths.len() {
+        if !step(depths[i - 1], depths[i]) {
+            start = i;
+        } else if i - start + 1 > best.len() {
+            best = Run { start, end: i };
+        }
+    }
+
+    best
+}
+
+// Only interior readings count, and plateaus are neither minima nor maxima
+fn local_extrema(depths: &[i32], cmp: impl Fn(i32, i32) -> bool) -> Vec<usize> {
+    depths
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| cmp(w[1], w[0]) && cmp(w[1], w[2]))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+impl fmt::Display for DepthReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let run = |r: &Run| format!("{} readings ({}..={})", r.len(), r.start, r.end);
+
+        writeln!(f, "Readings: {}", self.readings)?;
+        writeln!(f, "Depth increases: {}", self.depth_increases)?;
+        match self.sliding_increases {
+            Some(n) => writeln!(f, "Sliding window ({}) increases: {}", self.window, n)?,
+            None => writeln!(f, "Sliding window ({}) increases: n/a", self.window)?,
+        }
+        writeln!(f, "Longest increasing run: {}", run(&self.longest_increase))?;
+        writeln!(f, "Longest decreasing run: {}", run(&self.longest_decrease))?;
+        writeln!(f, "Local minima: {} at {:?}", self.local_minima.len(), self.local_minima)?;
+        writeln!(f, "Local maxima: {} at {:?}", self.local_maxima.len(), self.local_maxima)?;
+        writeln!(f, "Min: {}, max: {}, mean: {:.3}", self.min, self.max, self.mean)?;
+
+        write!(f, "Percentiles:")?;
+        for (p, d) in self.percentiles.iter() {
+            write!(f, " p{}={}", p, d)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Depth deltas:")?;
+        let widest = self.delta_histogram.values().copied().max().unwrap_or(0);
+        for (bin, &count) in self.delta_histogram.iter() {
+            let bar = "#".repeat((count * 50).div_ceil(widest));
+            writeln!(f, "{:6} ..= {:6} {:6} {}", bin, bin + self.bin_width - 1, count, bar)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+use crate::WindowError;
+
+pub struct DepthCounter {
+    size: usize,
+    window: VecDeque<i32>,
+    readings: usize,
+    depth_increases: usize,
+    sliding_increases: usize,
+}
+
+impl DepthCounter {
+    pub fn new(size: usize) -> Result<Self, WindowError> {
+        if size == 0 {
+            return Err(WindowError::ZeroSize);
+        }
+
+        Ok(Self {
+            size,
+            window: VecDeque::with_capacity(size),
+            readings: 0,
+            depth_increases: 0,
+            sliding_increases: 0,
+        })
+    }
+
+    pub fn push(&mut self, depth: i32) {
+        if let Some(&prev) = self.window.back() {
+            if depth > prev {
+                self.depth_increases += 1;
+            }
+        }
+
+        // Only the last `size` readings are kept; the oldest one is the
+        // reading that drops out when the window slides forward.
+        if self.window.len() == self.size {
+            if depth > self.window[0] {
+                self.sliding_increases += 1;
+            }
+            self.window.pop_front();
+        }
+
+        self.window.push_back(depth);
+        self.readings += 1;
+    }
+
+    pub fn readings(&self) -> usize {
+        self.readings
+    }
+
+    pub fn depth_increases(&self) -> usize {
+        self.depth_increases
+    }
+
+    pub fn sliding_increases(&self) -> Result<usize, WindowError> {
+        if self.readings < self.size {
+            return Err(WindowError::TooShort { window: self.size, len: self.readings });
+        }
+
+        Ok(self.sliding_increases)
+    }
+}
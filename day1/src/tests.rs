@@ -0,0 +1,172 @@
+use crate::*;
+
+const SAMPLE: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+#[test]
+fn test_window_of_one_matches_depth_increases() {
+    assert_eq!(count_sliding_increases(&SAMPLE, 1), Ok(count_depth_increases(&SAMPLE)));
+}
+
+#[test]
+fn test_too_few_readings() {
+    assert_eq!(count_depth_increases(&[]), 0);
+    assert_eq!(count_depth_increases(&[100]), 0);
+    assert_eq!(count_sliding_increases(&[], 3), Err(WindowError::TooShort { window: 3, len: 0 }));
+
+    // Skipping every bad line leaves nothing to count, which must not panic
+    let mut depths = Vec::new();
+    input::parse_depths("x\n\n".as_bytes(), "bad", &BadLinePolicy::Skip, |d| depths.push(d)).unwrap();
+    assert_eq!(count_depth_increases(&depths), 0);
+}
+
+#[test]
+fn test_window_of_three() {
+    assert_eq!(count_sliding_increases(&SAMPLE, 3), Ok(5));
+}
+
+#[test]
+fn test_window_as_long_as_input() {
+    assert_eq!(count_sliding_increases(&SAMPLE, 10), Ok(0));
+}
+
+#[test]
+fn test_window_too_long() {
+    assert_eq!(count_sliding_increases(&SAMPLE[..2], 3),
+               Err(WindowError::TooShort { window: 3, len: 2 }));
+}
+
+#[test]
+fn test_window_of_zero() {
+    assert_eq!(count_sliding_increases(&SAMPLE, 0), Err(WindowError::ZeroSize));
+}
+
+#[test]
+fn test_streaming_matches_batch() {
+    for size in 1..=5 {
+        let mut counter = DepthCounter::new(size).unwrap();
+        for &depth in SAMPLE.iter() {
+            counter.push(depth);
+        }
+
+        assert_eq!(counter.depth_increases(), count_depth_increases(&SAMPLE));
+        assert_eq!(counter.sliding_increases(), count_sliding_increases(&SAMPLE, size));
+    }
+}
+
+#[test]
+fn test_report() {
+    let report = DepthReport::new(&SAMPLE, 3, 10).unwrap();
+
+    assert_eq!(report.depth_increases, 7);
+    assert_eq!(report.sliding_increases, Some(5));
+    assert_eq!(report.longest_increase, report::Run { start: 0, end: 3 });
+    assert_eq!(report.longest_decrease, report::Run { start: 3, end: 4 });
+    assert_eq!(report.local_minima, vec![4, 8]);
+    assert_eq!(report.local_maxima, vec![3, 7]);
+    assert_eq!((report.min, report.max), (199, 269));
+    assert_eq!(report.percentiles[2], (50, 208));
+}
+
+#[test]
+fn test_glitch_repair() {
+    let depths = [100, 101, 102, 500, 104, 105, 0, 107];
+    let config = GlitchConfig { max_jump: Some(50), median_window: 5, median_tolerance: None };
+
+    let flagged = glitch::detect(&depths, &config);
+    assert_eq!(flagged, vec![3, 6]);
+
+    assert_eq!(glitch::repair(&depths, &flagged, &Repair::Interpolate),
+               vec![100, 101, 102, 103, 104, 105, 106, 107]);
+    assert_eq!(glitch::repair(&depths, &flagged, &Repair::Drop),
+               vec![100, 101, 102, 104, 105, 107]);
+
+    // A dropout can last more than one reading, but a step that stays is real
+    let dropout = [100, 101, 102, 0, 0, 105, 106, 107];
+    assert_eq!(glitch::detect(&dropout, &config), vec![3, 4]);
+    assert_eq!(glitch::repair(&dropout, &[3, 4], &Repair::Interpolate),
+               vec![100, 101, 102, 103, 104, 105, 106, 107]);
+    assert!(glitch::detect(&[100, 101, 102, 300, 301, 302, 303, 304], &config).is_empty());
+}
+
+#[test]
+fn test_bad_line_policies() {
+    let text = "199\n200\n\n208\n210\r";
+
+    let mut depths = Vec::new();
+    let result = input::parse_depths(text.as_bytes(), "sample", &BadLinePolicy::Fail, |d| depths.push(d));
+    match result {
+        Err(InputError::BadLine(bad)) => assert_eq!((bad.line, bad.text.as_str()), (3, "")),
+        _ => panic!("expected the blank line to fail"),
+    }
+
+    let mut depths = Vec::new();
+    let bad = input::parse_depths(text.as_bytes(), "sample", &BadLinePolicy::Collect, |d| depths.push(d)).unwrap();
+    assert_eq!(depths, vec![199, 200, 208]);
+    assert_eq!(bad.iter().map(|b| b.line).collect::<Vec<_>>(), vec![3, 5]);
+}
+
+#[test]
+fn test_align_and_merge() {
+    let mut seed: u32 = 7;
+    let trench: Vec<i32> = (0..60)
+        .map(|i| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            100 + i * 3 + (seed >> 16) as i32 % 11
+        })
+        .collect();
+    let first  = trench[..40].to_vec();
+    let second: Vec<i32> = trench[25..].iter().map(|d| d + 2).collect();
+
+    let alignment = align::align(&first, &second, 10).unwrap();
+    assert_eq!(alignment.overlap, 14);
+    assert_eq!(alignment.offset, 25);
+    assert!(alignment.confidence > 0.999);
+    assert_eq!(alignment.bias, 2.0);
+
+    // Without the bias taken out, the merge would step up by 2 where the
+    // overlap starts and end 2 too deep
+    let merged = align::merge(&[first, second], &[0, alignment.offset], &[0.0, alignment.bias]);
+    assert_eq!(merged, trench);
+    assert_eq!(count_depth_increases(&merged), count_depth_increases(&trench));
+
+    // The minimum overlap is never lowered to fit short sweeps
+    assert!(align::align(&trench[..40], &trench[25..], 40).is_none());
+    assert!(align::align(&trench[..4], &trench[..4], 1).is_none());
+}
+
+#[test]
+fn test_window_queries() {
+    let queries: Vec<Query> = ["sum:3", "sum:1:dec", "median:3:rise=5", "max:2:rise%=10"]
+        .iter()
+        .map(|q| q.parse().unwrap())
+        .collect();
+
+    let mut engine = WindowQueries::new(&queries);
+    for &depth in SAMPLE.iter() {
+        engine.push(depth);
+    }
+
+    let counts: Vec<usize> = engine.results().into_iter().map(|(_, r)| r.unwrap()).collect();
+    assert_eq!(counts, vec![5, 2, 3, 2]);
+    assert_eq!(engine.buffered(), 3);
+}
+
+#[test]
+fn test_window_queries_without_queries() {
+    // Streaming always feeds the engine, even when no query was asked for
+    let mut engine = WindowQueries::new(&[]);
+    for depth in 0..100_000 {
+        engine.push(depth);
+    }
+
+    assert_eq!(engine.buffered(), 0);
+    assert!(engine.results().is_empty());
+}
+
+#[test]
+fn test_bad_queries() {
+    assert!("sum".parse::<Query>().is_err());
+    assert!("avg:3".parse::<Query>().is_err());
+    assert!("sum:0".parse::<Query>().is_err());
+    assert!("sum:3:rise=x".parse::<Query>().is_err());
+}
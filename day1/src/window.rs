@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::WindowError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Mean,
+    Median,
+    Max,
+    Min,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Predicate {
+    Increase,
+    Decrease,
+    RiseBy(f64),
+    RisePercent(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub aggregate: Aggregate,
+    pub size: usize,
+    pub predicate: Predicate,
+}
+
+impl Aggregate {
+    fn apply<'a>(&self, values: impl Iterator<Item = &'a i32>) -> f64 {
+        let values: Vec<f64> = values.map(|&v| v as f64).collect();
+
+        match self {
+            Aggregate::Sum => values.iter().sum(),
+            Aggregate::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Aggregate::Max => values.iter().copied().fold(f64::MIN, f64::max),
+            Aggregate::Min => values.iter().copied().fold(f64::MAX, f64::min),
+            Aggregate::Median => {
+                let mut sorted = values;
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                let mid = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) {
+                    (sorted[mid - 1] + sorted[mid]) / 2.0
+                } else {
+                    sorted[mid]
+                }
+            },
+        }
+    }
+}
+
+impl Predicate {
+    fn holds(&self, prev: f64, current: f64) -> bool {
+        match *self {
+            Predicate::Increase => current > prev,
+            Predicate::Decrease => current < prev,
+            Predicate::RiseBy(threshold) => current - prev >= threshold,
+            Predicate::RisePercent(percent) => {
+                prev != 0.0 && (current - prev) / prev.abs() * 100.0 >= percent
+            },
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    // Queries look like "median:5:rise=2", the predicate defaults to "inc"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(format!("query '{}' is not of the form AGGREGATE:SIZE[:PREDICATE]", s));
+        }
+
+        let aggregate = match parts[0] {
+            "sum" => Aggregate::Sum,
+            "mean" => Aggregate::Mean,
+            "median" => Aggregate::Median,
+            "max" => Aggregate::Max,
+            "min" => Aggregate::Min,
+            a => return Err(format!("unknown aggregate '{}'", a)),
+        };
+
+        let size = match parts[1].parse() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(format!("invalid window size '{}'", parts[1])),
+        };
+
+        let threshold = |t: &str| t.parse().map_err(|_| format!("invalid threshold '{}'", t));
+
+        let predicate = match parts.get(2).map(|p| p.split_once('=').unwrap_or((p, ""))) {
+            None | Some(("inc", "")) => Predicate::Increase,
+            Some(("dec", "")) => Predicate::Decrease,
+            Some(("rise", t)) => Predicate::RiseBy(threshold(t)?),
+            Some(("rise%", t)) => Predicate::RisePercent(threshold(t)?),
+            Some(_) => return Err(format!("unknown predicate '{}'", parts[2])),
+        };
+
+        Ok(Query { aggregate, size, predicate })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let aggregate = match self.aggregate {
+            Aggregate::Sum => "sum",
+            Aggregate::Mean => "mean",
+            Aggregate::Median => "median",
+            Aggregate::Max => "max",
+            Aggregate::Min => "min",
+        };
+
+        write!(f, "{}-reading {} ", self.size, aggregate)?;
+
+        match self.predicate {
+            Predicate::Increase => write!(f, "increased"),
+            Predicate::Decrease => write!(f, "decreased"),
+            Predicate::RiseBy(t) => write!(f, "rose by at least {}", t),
+            Predicate::RisePercent(p) => write!(f, "rose by at least {}%", p),
+        }
+    }
+}
+
+// Evaluates any number of queries side by side while the readings pass
+// through once, keeping only as many readings as the widest window needs.
+pub struct WindowQueries {
+    queries: Vec<Query>,
+    readings: VecDeque<i32>,
+    capacity: usize,
+    seen: usize,
+    previous: Vec<Option<f64>>,
+    counts: Vec<usize>,
+}
+
+impl WindowQueries {
+    pub fn new(queries: &[Query]) -> Self {
+        let capacity = queries.iter().map(|q| q.size).max().unwrap_or(0);
+
+        Self {
+            queries: queries.to_vec(),
+            readings: VecDeque::with_capacity(capacity),
+            capacity,
+            seen: 0,
+            previous: vec![None; queries.len()],
+            counts: vec![0; queries.len()],
+        }
+    }
+
+    pub fn push(&mut self, depth: i32) {
+        self.seen += 1;
+
+        // Without queries there is nothing to keep, and streaming has to stay
+        // in constant memory
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(depth);
+
+        for (i, query) in self.queries.iter().enumerate() {
+            if self.readings.len() < query.size {
+                continue;
+            }
+
+            let window  = self.readings.iter().skip(self.readings.len() - query.size);
+            let current = query.aggregate.apply(window);
+
+            if let Some(prev) = self.previous[i] {
+                if query.predicate.holds(prev, current) {
+                    self.counts[i] += 1;
+                }
+            }
+
+            self.previous[i] = Some(current);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn buffered(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn results(&self) -> Vec<(&Query, Result<usize, WindowError>)> {
+        self.queries
+            .iter()
+            .zip(self.counts.iter())
+            .map(|(query, &count)| {
+                if self.seen < query.size {
+                    (query, Err(WindowError::TooShort { window: query.size, len: self.seen }))
+                } else {
+                    (query, Ok(count))
+                }
+            })
+            .collect()
+    }
+}
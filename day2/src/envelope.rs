@@ -0,0 +1,81 @@
+use std::fmt;
+
+use crate::{Command, Overflow};
+use crate::navigation::{self, NavigationModel, State};
+use crate::plan::AimLimits;
+
+#[derive(Default)]
+pub struct Envelope {
+    pub depth_min: i32,
+    pub depth_max: Option<i32>,
+    pub aim: AimLimits,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Breach {
+    TooShallow(i32),
+    TooDeep(i32),
+    AimTooLow(i32),
+    AimTooHigh(i32),
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    // 1-based, like the steps of a trace
+    pub step: usize,
+    pub command: Command,
+    pub state: State,
+    pub breach: Breach,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "command {} ({}): ", self.step, self.command)?;
+
+        match self.breach {
+            Breach::TooShallow(limit) => write!(f, "depth {} is shallower than {}", self.state.depth, limit)?,
+            Breach::TooDeep(limit) => write!(f, "depth {} is deeper than {}", self.state.depth, limit)?,
+            Breach::AimTooLow(limit) => write!(f, "aim {} is below {}", self.state.aim, limit)?,
+            Breach::AimTooHigh(limit) => write!(f, "aim {} is above {}", self.state.aim, limit)?,
+        }
+
+        write!(f, " (horizontal {}, depth {}, aim {})", self.state.horizontal, self.state.depth, self.state.aim)
+    }
+}
+
+impl Envelope {
+    fn breaches(&self, state: &State) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+
+        if state.depth < self.depth_min {
+            breaches.push(Breach::TooShallow(self.depth_min));
+        }
+        if let Some(max) = self.depth_max.filter(|&max| state.depth > max) {
+            breaches.push(Breach::TooDeep(max));
+        }
+        if let Some(min) = self.aim.min.filter(|&min| state.aim < min) {
+            breaches.push(Breach::AimTooLow(min));
+        }
+        if let Some(max) = self.aim.max.filter(|&max| state.aim > max) {
+            breaches.push(Breach::AimTooHigh(max));
+        }
+
+        breaches
+    }
+}
+
+pub fn check(model: &mut dyn NavigationModel, commands: &[Command], envelope: &Envelope)
+    -> Result<Vec<Violation>, Overflow>
+{
+    let mut violations = Vec::new();
+
+    for (i, command) in commands.iter().enumerate() {
+        let state = navigation::step(model, i + 1, command)?;
+
+        for breach in envelope.breaches(&state) {
+            violations.push(Violation { step: i + 1, command: command.clone(), state, breach });
+        }
+    }
+
+    Ok(violations)
+}
@@ -0,0 +1,80 @@
+use crate::{Command, Overflow};
+use crate::navigation::{self, NavigationModel, State};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub depth: i32,
+    pub aim: i32,
+    // Degrees counter-clockwise from the x axis, always within 0..360
+    pub heading: i32,
+}
+
+// Aim navigation on a plane: left and right turn the sub, and moving
+// forward follows the heading across x/y while diving along the aim
+#[derive(Default)]
+pub struct Steering {
+    pose: Pose,
+}
+
+impl Steering {
+    fn travel(&mut self, amount: f64, depth: Option<i32>) -> bool {
+        let p = &mut self.pose;
+        let radians = (p.heading as f64).to_radians();
+
+        match depth {
+            Some(depth) => p.depth = depth,
+            None => return false,
+        }
+        p.x += amount * radians.cos();
+        p.y += amount * radians.sin();
+        true
+    }
+
+    fn turn(&mut self, degrees: i32) -> bool {
+        self.pose.heading = (self.pose.heading + degrees.rem_euclid(360)) % 360;
+        true
+    }
+
+    fn set_aim(&mut self, aim: Option<i32>) -> bool {
+        aim.map(|aim| self.pose.aim = aim).is_some()
+    }
+
+    pub fn pose(&self) -> Pose {
+        self.pose
+    }
+}
+
+impl NavigationModel for Steering {
+    fn apply(&mut self, command: &Command) -> bool {
+        let p = self.pose;
+
+        match command {
+            Command::Forward(amount) => self.travel(*amount as f64, navigation::dive(p.depth, p.aim, *amount)),
+            Command::Back(amount) => self.travel(-(*amount as f64), navigation::surface(p.depth, p.aim, *amount)),
+            Command::Down(amount) => self.set_aim(p.aim.checked_add(*amount)),
+            Command::Up(amount) => self.set_aim(p.aim.checked_sub(*amount)),
+            Command::AimReset => self.set_aim(Some(0)),
+            Command::Left(degrees) => self.turn(*degrees),
+            Command::Right(degrees) => self.turn(360 - degrees.rem_euclid(360)),
+        }
+    }
+
+    // The generic state only knows about one horizontal axis, so it gets
+    // the distance covered along x
+    fn state(&self) -> State {
+        State {
+            horizontal: self.pose.x.round() as i32,
+            depth: self.pose.depth,
+            aim: self.pose.aim,
+        }
+    }
+}
+
+pub fn steer(commands: &[Command]) -> Result<Pose, Overflow> {
+    let mut steering = Steering::default();
+    navigation::run(&mut steering, commands)?;
+
+    Ok(steering.pose())
+}
@@ -1,76 +1,318 @@
+#[cfg(test)]
+mod tests;
+mod envelope;
+mod heading;
+mod navigation;
+mod plan;
+mod script;
+mod trace;
+
 use std::env;
-use std::io::{self, BufReader, prelude::*};
-use std::fs::File;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use crate::envelope::Envelope;
+use crate::navigation::{Aimed, Plain, Registry};
+use crate::plan::{AimLimits, Objective};
+use crate::script::ParseError;
+use crate::trace::{TraceFormat, TraceWriter};
 
+#[derive(Debug, Clone, PartialEq)]
 enum Command {
     Forward(i32),
     Down(i32),
     Up(i32),
+    Back(i32),
+    AimReset,
+    Left(i32),
+    Right(i32),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Forward(amount) => write!(f, "forward {}", amount),
+            Command::Down(amount) => write!(f, "down {}", amount),
+            Command::Up(amount) => write!(f, "up {}", amount),
+            Command::Back(amount) => write!(f, "back {}", amount),
+            Command::AimReset => write!(f, "aim-reset"),
+            Command::Left(degrees) => write!(f, "left {}", degrees),
+            Command::Right(degrees) => write!(f, "right {}", degrees),
+        }
+    }
 }
 
-fn read_input(fname: &str) -> io::Result<Vec<Command>> {
-    let f = File::open(fname)?;
-    let reader = BufReader::new(f);
+// A command took the position or aim past what an i32 can hold
+#[derive(Debug, Clone, PartialEq)]
+struct Overflow {
+    // 1-based index into the expanded commands
+    step: usize,
+    command: Command,
+}
 
-    let mut commands = Vec::new();
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "command {} ({}) takes the sub out of range", self.step, self.command)
+    }
+}
 
-    for line in reader.lines() {
-        let l = line?;
-        let s: Vec<&str> = l.split_whitespace().collect();
-        let value: i32   = s[1].parse().unwrap();
+#[derive(Debug)]
+enum InputError {
+    Io(io::Error),
+    Parse(ParseError),
+}
 
-        match s[0] {
-            "forward" => commands.push(Command::Forward(value)),
-            "down" => commands.push(Command::Down(value)),
-            "up" => commands.push(Command::Up(value)),
-            _ => panic!("Illegal command '{}'!", s[0]),
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io(e) => write!(f, "{}", e),
+            InputError::Parse(e) => write!(f, "{}", e),
         }
     }
+}
+
+fn read_input(fname: &str) -> Result<Vec<Command>, InputError> {
+    let text = fs::read_to_string(fname).map_err(InputError::Io)?;
+    script::parse(&text).map_err(InputError::Parse)
+}
 
-    Ok(commands)
+fn dive1(commands: &[Command]) -> Result<(i32, i32), Overflow> {
+    let state = navigation::run(&mut Plain::default(), commands)?;
+    Ok((state.horizontal, state.depth))
 }
 
-fn dive1(commands: &[Command]) -> (i32, i32) {
-    let mut horizontal = 0;
-    let mut depth = 0;
+fn dive2(commands: &[Command]) -> Result<(i32, i32), Overflow> {
+    let state = navigation::run(&mut Aimed::default(), commands)?;
+    Ok((state.horizontal, state.depth))
+}
 
-    for command in commands.iter() {
-        match command {
-            Command::Forward(amount) => horizontal += amount,
-            Command::Down(amount) => depth += amount,
-            Command::Up(amount) => depth -= amount,
-        }
+// Two i32 coordinates always multiply within an i64
+fn product(horizontal: i32, depth: i32) -> i64 {
+    horizontal as i64 * depth as i64
+}
+
+struct Options {
+    fname: Option<String>,
+    models: Vec<String>,
+    steer: bool,
+    trace: Option<TraceFormat>,
+    trace_every: usize,
+    trace_out: Option<String>,
+    plan: Option<(i32, i32)>,
+    objective: Objective,
+    aim_limits: AimLimits,
+    check: bool,
+    depth_min: i32,
+    depth_max: Option<i32>,
+}
+
+fn usage(registry: &Registry) -> ! {
+    eprintln!("Usage: day2 <input> [--model NAME[:PARAM]]... [--3d]");
+    eprintln!("                   [--trace csv|json [--trace-every N] [--trace-out FILE]]");
+    eprintln!("                   [--check [--depth-min N] [--depth-max N] [--aim-min N] [--aim-max N]]");
+    eprintln!("       day2 --plan HORIZONTAL DEPTH [--minimize count|magnitude]");
+    eprintln!("                   [--aim-min N] [--aim-max N]");
+    eprintln!("Models:");
+    for (name, description) in registry.describe() {
+        eprintln!("  {:<10} {}", name, description);
     }
+    process::exit(1);
+}
 
-    (horizontal, depth)
+fn fail(e: impl fmt::Display) -> ! {
+    eprintln!("Error: {}", e);
+    process::exit(1);
 }
 
-fn dive2(commands: &[Command]) -> (i32, i32) {
-    let mut horizontal = 0;
-    let mut depth = 0;
-    let mut aim = 0;
+fn next_number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, registry: &Registry) -> T {
+    match args.next().and_then(|n| n.parse().ok()) {
+        Some(n) => n,
+        None => usage(registry),
+    }
+}
+
+fn parse_args(registry: &Registry) -> Options {
+    let mut args = env::args().skip(1);
+
+    let mut fname  = None;
+    let mut models = Vec::new();
+    let mut trace  = None;
+    let mut trace_every = 1;
+    let mut trace_out   = None;
+    let mut steer = false;
+    let mut plan  = None;
+    let mut objective  = Objective::Count;
+    let mut aim_limits = AimLimits::default();
+    let mut check = false;
+    let mut depth_min = 0;
+    let mut depth_max = None;
 
-    for command in commands.iter() {
-        match command {
-            Command::Forward(amount) => {
-                horizontal += amount;
-                depth += aim * amount;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => models.push(args.next().unwrap_or_else(|| usage(registry))),
+            "--trace" => {
+                trace = match args.next().as_deref() {
+                    Some("csv") => Some(TraceFormat::Csv),
+                    Some("json") => Some(TraceFormat::Json),
+                    _ => usage(registry),
+                };
             },
-            Command::Down(amount) => aim += amount,
-            Command::Up(amount) => aim -= amount,
+            "--trace-every" => {
+                trace_every = match next_number(&mut args, registry) {
+                    0 => usage(registry),
+                    n => n,
+                };
+            },
+            "--trace-out" => trace_out = Some(args.next().unwrap_or_else(|| usage(registry))),
+            "--3d" => steer = true,
+            "--plan" => plan = Some((next_number(&mut args, registry), next_number(&mut args, registry))),
+            "--minimize" => {
+                objective = match args.next().as_deref() {
+                    Some("count") => Objective::Count,
+                    Some("magnitude") => Objective::Magnitude,
+                    _ => usage(registry),
+                };
+            },
+            "--aim-min" => aim_limits.min = Some(next_number(&mut args, registry)),
+            "--aim-max" => aim_limits.max = Some(next_number(&mut args, registry)),
+            "--check" => check = true,
+            "--depth-min" => depth_min = next_number(&mut args, registry),
+            "--depth-max" => depth_max = Some(next_number(&mut args, registry)),
+            _ if fname.is_none() => fname = Some(arg),
+            _ => usage(registry),
+        }
+    }
+
+    if fname.is_none() && plan.is_none() {
+        usage(registry);
+    }
+
+    Options {
+        fname, models, steer, trace, trace_every, trace_out, plan, objective, aim_limits, check, depth_min,
+        depth_max,
+    }
+}
+
+fn write_trace(registry: &Registry, options: &Options, format: TraceFormat, commands: &[Command])
+    -> io::Result<()>
+{
+    let out: Box<dyn Write> = match &options.trace_out {
+        Some(fname) => Box::new(BufWriter::new(File::create(fname)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    // Without explicit models, trace both of the original interpretations
+    let specs = if options.models.is_empty() {
+        vec!["plain".to_string(), "aim".to_string()]
+    } else {
+        options.models.clone()
+    };
+
+    let mut writer = TraceWriter::new(out, format)?;
+    for spec in specs.iter() {
+        let mut model = registry.create(spec).unwrap_or_else(|e| fail(e));
+        writer.trace(spec, model.as_mut(), commands, options.trace_every)?;
+    }
+
+    writer.finish()
+}
+
+// Returns whether every model kept the route inside the envelope
+fn check_envelope(registry: &Registry, options: Options, commands: &[Command]) -> bool {
+    let specs = if options.models.is_empty() {
+        vec!["plain".to_string(), "aim".to_string()]
+    } else {
+        options.models
+    };
+
+    let envelope = Envelope { depth_min: options.depth_min, depth_max: options.depth_max, aim: options.aim_limits };
+    let mut safe = true;
+
+    for spec in specs.iter() {
+        let mut model  = registry.create(spec).unwrap_or_else(|e| fail(e));
+        let violations = envelope::check(model.as_mut(), commands, &envelope)
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", spec, e)));
+
+        if violations.is_empty() {
+            println!("{}: route stays within the safety envelope", spec);
+        } else {
+            println!("{}: {} violation(s)", spec, violations.len());
+            for violation in violations.iter() {
+                println!("  {}", violation);
+            }
+            safe = false;
         }
     }
 
-    (horizontal, depth)
+    safe
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let commands = read_input(&args[1]).unwrap();
+    let registry = Registry::with_builtins();
+    let mut options = parse_args(&registry);
+
+    if let Some((horizontal, depth)) = options.plan {
+        match plan::plan(horizontal, depth, &options.objective, &options.aim_limits) {
+            Ok(commands) => {
+                println!("# {} commands, total magnitude {}", commands.len(), plan::magnitude(&commands));
+                for command in commands.iter() {
+                    println!("{}", command);
+                }
+            },
+            Err(e) => fail(e),
+        }
+
+        return;
+    }
+
+    let fname = options.fname.clone().unwrap_or_default();
+    let commands = match read_input(&fname) {
+        Ok(commands) => commands,
+        Err(e) => fail(format_args!("{}:{}", fname, e)),
+    };
+
+    if let Some(format) = options.trace.take() {
+        if let Err(e) = write_trace(&registry, &options, format, &commands) {
+            fail(e);
+        }
+
+        // A trace on stdout is meant to be piped, so keep it clean
+        if options.trace_out.is_none() {
+            return;
+        }
+    }
+
+    if options.check {
+        if !check_envelope(&registry, options, &commands) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if options.steer {
+        let pose = heading::steer(&commands).unwrap_or_else(|e| fail(e));
+        println!("x: {:.2}, y: {:.2}, depth: {}, heading: {} degrees", pose.x, pose.y, pose.depth, pose.heading);
+        return;
+    }
+
+    if !options.models.is_empty() {
+        for spec in options.models.iter() {
+            let mut model = registry.create(spec).unwrap_or_else(|e| fail(e));
+            let state = navigation::run(model.as_mut(), &commands)
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", spec, e)));
+
+            println!("{}: horizontal position: {}, depth: {} -> Result: {}",
+                     spec, state.horizontal, state.depth, product(state.horizontal, state.depth));
+        }
+
+        return;
+    }
 
-    let (h1, d1) = dive1(&commands);
-    println!("Horizontal position: {}, depth: {} -> Result: {}", h1, d1, h1 * d1);
+    let (h1, d1) = dive1(&commands).unwrap_or_else(|e| fail(e));
+    println!("Horizontal position: {}, depth: {} -> Result: {}", h1, d1, product(h1, d1));
 
-    let (h2, d2) = dive2(&commands);
-    println!("Horizontal position: {}, depth: {} -> Result: {}", h2, d2, h2 * d2);
+    let (h2, d2) = dive2(&commands).unwrap_or_else(|e| fail(e));
+    println!("Horizontal position: {}, depth: {} -> Result: {}", h2, d2, product(h2, d2));
 }
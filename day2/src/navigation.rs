@@ -0,0 +1,225 @@
+use crate::{Command, Overflow};
+use crate::heading::Steering;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct State {
+    pub horizontal: i32,
+    pub depth: i32,
+    pub aim: i32,
+}
+
+pub trait NavigationModel {
+    // Returns false, leaving the state unspecified, when the command overflows
+    fn apply(&mut self, command: &Command) -> bool;
+    fn state(&self) -> State;
+}
+
+pub fn step(model: &mut dyn NavigationModel, step: usize, command: &Command) -> Result<State, Overflow> {
+    if model.apply(command) {
+        Ok(model.state())
+    } else {
+        Err(Overflow { step, command: command.clone() })
+    }
+}
+
+pub fn run(model: &mut dyn NavigationModel, commands: &[Command]) -> Result<State, Overflow> {
+    for (i, command) in commands.iter().enumerate() {
+        step(model, i + 1, command)?;
+    }
+
+    Ok(model.state())
+}
+
+// The depth after moving `amount` forward at `aim`, or back for `surface`
+pub fn dive(depth: i32, aim: i32, amount: i32) -> Option<i32> {
+    aim.checked_mul(amount).and_then(|change| depth.checked_add(change))
+}
+
+pub fn surface(depth: i32, aim: i32, amount: i32) -> Option<i32> {
+    aim.checked_mul(amount).and_then(|change| depth.checked_sub(change))
+}
+
+// Up and down change the depth directly
+#[derive(Default)]
+pub struct Plain {
+    state: State,
+}
+
+impl NavigationModel for Plain {
+    fn apply(&mut self, command: &Command) -> bool {
+        let s = &mut self.state;
+
+        let next = match command {
+            Command::Forward(amount) => s.horizontal.checked_add(*amount).map(|horizontal| State { horizontal, ..*s }),
+            Command::Down(amount) => s.depth.checked_add(*amount).map(|depth| State { depth, ..*s }),
+            Command::Up(amount) => s.depth.checked_sub(*amount).map(|depth| State { depth, ..*s }),
+            Command::Back(amount) => s.horizontal.checked_sub(*amount).map(|horizontal| State { horizontal, ..*s }),
+            Command::AimReset | Command::Left(_) | Command::Right(_) => Some(*s),
+        };
+
+        next.map(|next| *s = next).is_some()
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+}
+
+// Up and down change the aim, moving forward dives along it
+#[derive(Default)]
+pub struct Aimed {
+    state: State,
+}
+
+impl NavigationModel for Aimed {
+    fn apply(&mut self, command: &Command) -> bool {
+        let s = &mut self.state;
+
+        let next = match command {
+            Command::Forward(amount) => s.horizontal.checked_add(*amount).zip(dive(s.depth, s.aim, *amount))
+                .map(|(horizontal, depth)| State { horizontal, depth, ..*s }),
+            Command::Back(amount) => s.horizontal.checked_sub(*amount).zip(surface(s.depth, s.aim, *amount))
+                .map(|(horizontal, depth)| State { horizontal, depth, ..*s }),
+            Command::Down(amount) => s.aim.checked_add(*amount).map(|aim| State { aim, ..*s }),
+            Command::Up(amount) => s.aim.checked_sub(*amount).map(|aim| State { aim, ..*s }),
+            Command::AimReset => Some(State { aim: 0, ..*s }),
+            Command::Left(_) | Command::Right(_) => Some(*s),
+        };
+
+        next.map(|next| *s = next).is_some()
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+}
+
+// Like `Aimed`, but a current pushes the sub down by `drift` for every unit
+// it travels, whatever the aim
+pub struct Drift {
+    aimed: Aimed,
+    drift: i32,
+}
+
+impl NavigationModel for Drift {
+    fn apply(&mut self, command: &Command) -> bool {
+        if !self.aimed.apply(command) {
+            return false;
+        }
+
+        let depth = &mut self.aimed.state.depth;
+        let drifted = match command {
+            Command::Forward(amount) => dive(*depth, self.drift, *amount),
+            Command::Back(amount) => surface(*depth, self.drift, *amount),
+            _ => return true,
+        };
+
+        drifted.map(|drifted| *depth = drifted).is_some()
+    }
+
+    fn state(&self) -> State {
+        self.aimed.state
+    }
+}
+
+// Like `Aimed`, but the sub can neither surface nor go deeper than `limit`
+pub struct DepthLimited {
+    aimed: Aimed,
+    limit: i32,
+}
+
+impl NavigationModel for DepthLimited {
+    fn apply(&mut self, command: &Command) -> bool {
+        if !self.aimed.apply(command) {
+            return false;
+        }
+
+        let depth = &mut self.aimed.state.depth;
+        *depth = (*depth).clamp(0, self.limit);
+        true
+    }
+
+    fn state(&self) -> State {
+        self.aimed.state
+    }
+}
+
+pub type Constructor = fn(Option<&str>) -> Result<Box<dyn NavigationModel>, String>;
+
+#[derive(Default)]
+pub struct Registry {
+    models: Vec<(&'static str, &'static str, Constructor)>,
+}
+
+fn parameter(name: &str, param: Option<&str>) -> Result<i32, String> {
+    match param.map(|p| p.parse()) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) => Err(format!("model '{}' needs a whole number parameter", name)),
+        None => Err(format!("model '{}' needs a parameter, e.g. '{}:10'", name, name)),
+    }
+}
+
+fn no_parameter(name: &str, param: Option<&str>) -> Result<(), String> {
+    match param {
+        Some(_) => Err(format!("model '{}' takes no parameter", name)),
+        None => Ok(()),
+    }
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+
+        registry.register("plain", "up and down change the depth", |param| {
+            no_parameter("plain", param)?;
+            Ok(Box::new(Plain::default()))
+        });
+        registry.register("aim", "up and down change the aim", |param| {
+            no_parameter("aim", param)?;
+            Ok(Box::new(Aimed::default()))
+        });
+        registry.register("heading", "aim, with left and right turning the sub (reports x)", |param| {
+            no_parameter("heading", param)?;
+            Ok(Box::new(Steering::default()))
+        });
+        registry.register("drift", "aim, plus DRIFT depth per unit travelled (drift:DRIFT)", |param| {
+            let drift = parameter("drift", param)?;
+            Ok(Box::new(Drift { aimed: Aimed::default(), drift }))
+        });
+        registry.register("limited", "aim, with the depth kept between 0 and LIMIT (limited:LIMIT)", |param| {
+            let limit = parameter("limited", param)?;
+            if limit < 0 {
+                return Err("model 'limited' needs a limit of at least 0".to_string());
+            }
+            Ok(Box::new(DepthLimited { aimed: Aimed::default(), limit }))
+        });
+
+        registry
+    }
+
+    pub fn register(&mut self, name: &'static str, description: &'static str, constructor: Constructor) {
+        self.models.retain(|(n, _, _)| *n != name);
+        self.models.push((name, description, constructor));
+    }
+
+    // Models are selected as "name" or "name:parameter"
+    pub fn create(&self, spec: &str) -> Result<Box<dyn NavigationModel>, String> {
+        let (name, param) = match spec.split_once(':') {
+            Some((name, param)) => (name, Some(param)),
+            None => (spec, None),
+        };
+
+        match self.models.iter().find(|(n, _, _)| *n == name) {
+            Some((_, _, constructor)) => constructor(param),
+            None => Err(format!("unknown navigation model '{}'", name)),
+        }
+    }
+
+    pub fn describe(&self) -> Vec<(&'static str, &'static str)> {
+        self.models.iter().map(|&(name, description, _)| (name, description)).collect()
+    }
+}
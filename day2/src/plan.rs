@@ -0,0 +1,176 @@
+use std::fmt;
+
+use crate::{Command, dive2};
+use crate::navigation::{Aimed, NavigationModel};
+
+pub enum Objective {
+    Count,
+    Magnitude,
+}
+
+#[derive(Default)]
+pub struct AimLimits {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    Backwards,
+    NoTravel,
+    AimAboveMax { needed: i64, max: i32 },
+    AimBelowMin { needed: i64, min: i32 },
+    TooLarge,
+    Verification,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::Backwards => write!(f, "the target lies behind the start"),
+            PlanError::NoTravel => write!(f, "the depth can only change while moving forward"),
+            PlanError::AimAboveMax { needed, max } => {
+                write!(f, "reaching the target needs an aim of at least {}, above the maximum of {}", needed, max)
+            },
+            PlanError::AimBelowMin { needed, min } => {
+                write!(f, "reaching the target needs an aim of at most {}, below the minimum of {}", needed, min)
+            },
+            PlanError::TooLarge => {
+                write!(f, "reaching the target needs commands larger than {}", i32::MAX)
+            },
+            PlanError::Verification => write!(f, "the planned route misses the target"),
+        }
+    }
+}
+
+impl AimLimits {
+    pub fn allows(&self, aim: i64) -> bool {
+        self.min.is_none_or(|min| aim >= min as i64) && self.max.is_none_or(|max| aim <= max as i64)
+    }
+}
+
+pub fn magnitude(commands: &[Command]) -> i64 {
+    commands
+        .iter()
+        .map(|c| match c {
+            Command::Forward(n) | Command::Down(n) | Command::Up(n) | Command::Back(n)
+                | Command::Left(n) | Command::Right(n) => n.unsigned_abs() as i64,
+            Command::AimReset => 0,
+        })
+        .sum()
+}
+
+// Builds the commands for a route that starts level and then moves forward
+// the given distance at each aim in turn, or nothing if an amount does not
+// fit into a command
+fn route(legs: &[(i64, i64)]) -> Option<Vec<Command>> {
+    let mut commands = Vec::new();
+    let mut aim = 0;
+
+    for &(leg_aim, forward) in legs.iter() {
+        if forward == 0 {
+            continue;
+        }
+
+        if leg_aim > aim {
+            commands.push(Command::Down(i32::try_from(leg_aim - aim).ok()?));
+        } else if leg_aim < aim {
+            commands.push(Command::Up(i32::try_from(aim - leg_aim).ok()?));
+        }
+
+        commands.push(Command::Forward(i32::try_from(forward).ok()?));
+        aim = leg_aim;
+    }
+
+    Some(commands)
+}
+
+// Every route with at most three commands has one of the shapes "forward",
+// "aim, forward" or "forward, aim, forward". When none of them fits, the
+// route that spends part of the way one short of the steepest aim needs
+// four commands and is also the one with the smallest total magnitude.
+fn candidates(horizontal: i64, depth: i64) -> Vec<Vec<Command>> {
+    if depth == 0 {
+        return route(&[(0, horizontal)]).into_iter().collect();
+    }
+
+    let mut routes = Vec::new();
+    let sign = depth.signum();
+    let a = depth.abs();
+
+    if a % horizontal == 0 {
+        routes.extend(route(&[(depth / horizontal, horizontal)]));
+    }
+
+    let mut d = 1;
+    while d * d <= a {
+        if a % d == 0 {
+            for f in [d, a / d] {
+                if f < horizontal {
+                    routes.extend(route(&[(0, horizontal - f), (sign * (a / f), f)]));
+                }
+            }
+        }
+        d += 1;
+    }
+
+    let steepest = (a + horizontal - 1) / horizontal;
+    let f = a - horizontal * (steepest - 1);
+    routes.extend(route(&[(sign * (steepest - 1), horizontal - f), (sign * steepest, f)]));
+
+    routes
+}
+
+fn within_limits(commands: &[Command], limits: &AimLimits) -> bool {
+    let mut model = Aimed::default();
+
+    commands.iter().all(|command| model.apply(command) && limits.allows(model.state().aim as i64))
+}
+
+pub fn plan(horizontal: i32, depth: i32, objective: &Objective, limits: &AimLimits)
+    -> Result<Vec<Command>, PlanError>
+{
+    let (h, d) = (horizontal as i64, depth as i64);
+
+    if h < 0 {
+        return Err(PlanError::Backwards);
+    }
+
+    if h == 0 {
+        return if d == 0 { Ok(Vec::new()) } else { Err(PlanError::NoTravel) };
+    }
+
+    // Moving forward at aims between the limits reaches any depth between
+    // the shallowest and the steepest aim times the distance
+    if let Some(max) = limits.max.filter(|&max| d > h * max as i64) {
+        return Err(PlanError::AimAboveMax { needed: -(-d).div_euclid(h), max });
+    }
+    if let Some(min) = limits.min.filter(|&min| d < h * min as i64) {
+        return Err(PlanError::AimBelowMin { needed: d.div_euclid(h), min });
+    }
+
+    let candidates = candidates(h, d);
+    if candidates.is_empty() {
+        return Err(PlanError::TooLarge);
+    }
+
+    let routes = candidates
+        .into_iter()
+        .filter(|commands| within_limits(commands, limits));
+
+    let best = match objective {
+        Objective::Count => routes.min_by_key(|c| (c.len(), magnitude(c))),
+        Objective::Magnitude => routes.min_by_key(|c| (magnitude(c), c.len())),
+    };
+
+    let commands = match best {
+        Some(commands) => commands,
+        None => return Err(PlanError::Verification),
+    };
+
+    if dive2(&commands) != Ok((horizontal, depth)) {
+        return Err(PlanError::Verification);
+    }
+
+    Ok(commands)
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+
+use crate::Command;
+
+// Repeat blocks are expanded up front, so nested ones could otherwise ask
+// for more commands than fit into memory
+pub const MAX_COMMANDS: usize = 1_000_000;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Number(String),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    col: usize,
+}
+
+impl Token {
+    fn width(&self) -> usize {
+        match &self.kind {
+            TokenKind::Word(w) | TokenKind::Number(w) => w.chars().count(),
+            _ => 1,
+        }
+    }
+}
+
+fn error<T>(line: usize, col: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, col, message })
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut j = 0;
+
+        while j < chars.len() {
+            let c = chars[j];
+            let (lineno, col) = (i + 1, j + 1);
+
+            if c == '#' {
+                break;
+            } else if c.is_whitespace() {
+                j += 1;
+                continue;
+            } else if c == '{' || c == '}' {
+                let kind = if c == '{' { TokenKind::Open } else { TokenKind::Close };
+                tokens.push(Token { kind, line: lineno, col });
+                j += 1;
+                continue;
+            }
+
+            let start = j;
+            while j < chars.len() && !chars[j].is_whitespace() && !"{}#".contains(chars[j]) {
+                j += 1;
+            }
+            let word: String = chars[start..j].iter().collect();
+
+            let kind = if word.chars().all(|c| c.is_ascii_digit() || c == '-') && word.chars().any(|c| c.is_ascii_digit()) {
+                TokenKind::Number(word)
+            } else if c.is_ascii_alphabetic() && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                TokenKind::Word(word)
+            } else {
+                return error(lineno, col, format!("unexpected '{}'", word));
+            };
+
+            tokens.push(Token { kind, line: lineno, col });
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn number(&mut self, after: &Token) -> Result<i32, ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(Token { kind: TokenKind::Number(n), line, col }) if *line == after.line => {
+                let (line, col) = (*line, *col);
+                self.pos += 1;
+                n.parse().or_else(|_| error(line, col, format!("'{}' is not a valid number", n)))
+            },
+            Some(Token { line, col, .. }) if *line == after.line => {
+                error(*line, *col, "expected a number".to_string())
+            },
+            _ => error(after.line, after.col + after.width(), "expected a number".to_string()),
+        }
+    }
+
+    fn block(&mut self, commands: &mut Vec<Command>, opened: Option<&Token>) -> Result<(), ParseError> {
+        while self.pos < self.tokens.len() {
+            let token = self.tokens[self.pos].clone();
+            let (line, col) = (token.line, token.col);
+            self.pos += 1;
+
+            let word = match &token.kind {
+                TokenKind::Word(w) => w,
+                TokenKind::Close if opened.is_some() => return Ok(()),
+                TokenKind::Close => return error(line, col, "unmatched '}'".to_string()),
+                TokenKind::Open => return error(line, col, "unexpected '{'".to_string()),
+                TokenKind::Number(n) => return error(line, col, format!("expected a command, found '{}'", n)),
+            };
+
+            match word.as_str() {
+                "forward" => commands.push(Command::Forward(self.number(&token)?)),
+                "down" => commands.push(Command::Down(self.number(&token)?)),
+                "up" => commands.push(Command::Up(self.number(&token)?)),
+                "back" => commands.push(Command::Back(self.number(&token)?)),
+                "aim-reset" => commands.push(Command::AimReset),
+                "left" => commands.push(Command::Left(self.number(&token)?)),
+                "right" => commands.push(Command::Right(self.number(&token)?)),
+                "repeat" => {
+                    let times = self.number(&token)?;
+                    if times < 0 {
+                        return error(line, col, format!("cannot repeat {} times", times));
+                    }
+
+                    match self.tokens.get(self.pos) {
+                        Some(Token { kind: TokenKind::Open, .. }) => self.pos += 1,
+                        Some(t) => return error(t.line, t.col, "expected '{' after repeat count".to_string()),
+                        None => {
+                            let count = &self.tokens[self.pos - 1];
+                            return error(count.line, count.col + count.width(),
+                                         "expected '{' after repeat count".to_string());
+                        },
+                    }
+
+                    let mut body = Vec::new();
+                    self.block(&mut body, Some(&token))?;
+
+                    let total = body
+                        .len()
+                        .checked_mul(times as usize)
+                        .and_then(|n| n.checked_add(commands.len()))
+                        .filter(|&n| n <= MAX_COMMANDS);
+                    if total.is_none() {
+                        return error(line, col, format!("repeat expands to more than {} commands", MAX_COMMANDS));
+                    }
+
+                    for _ in 0..times {
+                        commands.extend(body.iter().cloned());
+                    }
+                },
+                _ => return error(line, col, format!("unknown command '{}'", word)),
+            }
+        }
+
+        match opened {
+            Some(t) => error(t.line, t.col, "repeat block is never closed".to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Command>, ParseError> {
+    let mut parser   = Parser { tokens: tokenize(text)?, pos: 0 };
+    let mut commands = Vec::new();
+    parser.block(&mut commands, None)?;
+
+    Ok(commands)
+}
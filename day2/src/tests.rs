@@ -0,0 +1,210 @@
+use crate::*;
+use crate::envelope::Breach;
+use crate::navigation::State;
+use crate::plan::PlanError;
+use crate::trace::{TraceFormat, TraceWriter};
+
+const SAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2\n";
+
+#[test]
+fn test_parse_plain_commands() {
+    let commands = script::parse(SAMPLE).unwrap();
+    assert_eq!(commands.len(), 6);
+    assert_eq!(dive1(&commands), Ok((15, 10)));
+    assert_eq!(dive2(&commands), Ok((15, 60)));
+}
+
+#[test]
+fn test_parse_comments_and_repeats() {
+    let text = "# a comment\n\nrepeat 2 {\n  forward 1 # trailing\n  repeat 3 { down 1 }\n}\naim-reset\nback 2\n";
+    let commands = script::parse(text).unwrap();
+
+    assert_eq!(commands.len(), 10);
+    assert_eq!(commands[1..4], [Command::Down(1), Command::Down(1), Command::Down(1)]);
+    assert_eq!(commands[8..], [Command::AimReset, Command::Back(2)]);
+}
+
+#[test]
+fn test_overflow() {
+    let commands = script::parse("repeat 1000 { down 1000 forward 1000 }").unwrap();
+
+    assert_eq!(dive1(&commands), Ok((1_000_000, 1_000_000)));
+    assert_eq!(product(1_000_000, 1_000_000), 1_000_000_000_000);
+    assert_eq!(dive2(&commands), Err(Overflow { step: 132, command: Command::Forward(1000) }));
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = |text| script::parse(text).unwrap_err();
+
+    assert_eq!(error("forward 5\nsideways 3\n"),
+               ParseError { line: 2, col: 1, message: "unknown command 'sideways'".to_string() });
+    assert_eq!((error("forward\n5\n").line, error("forward\n5\n").col), (1, 8));
+    assert_eq!((error("repeat 2 {\n  up 1\n").line, error("repeat 2 {\n  up 1\n").col), (1, 1));
+    assert_eq!((error("down 1 }").line, error("down 1 }").col), (1, 8));
+    assert_eq!((error("down x1").line, error("down x1").col), (1, 6));
+
+    // Nested repeats are refused before they are expanded
+    let huge = error("up 1\nrepeat 100000 { repeat 100000 { forward 1 } }\n");
+    assert_eq!((huge.line, huge.col), (2, 1));
+    let overflow = error("repeat 2147483647 { repeat 2147483647 { repeat 2147483647 { forward 1 } } }");
+    assert_eq!((overflow.line, overflow.col), (1, 41));
+    assert_eq!(script::parse("repeat 1000 { repeat 1000 { forward 1 } }").unwrap().len(), script::MAX_COMMANDS);
+}
+
+#[test]
+fn test_registry() {
+    let registry = Registry::with_builtins();
+    let commands = script::parse(SAMPLE).unwrap();
+
+    let run = |spec| navigation::run(registry.create(spec).unwrap().as_mut(), &commands).unwrap();
+
+    assert_eq!(Ok((run("plain").horizontal, run("plain").depth)), dive1(&commands));
+    assert_eq!(Ok((run("aim").horizontal, run("aim").depth)), dive2(&commands));
+    assert_eq!(run("drift:1").depth, 60 + 15);
+    assert_eq!(run("limited:40").depth, 40);
+
+    let mut limited = registry.create("limited:40").unwrap();
+    let overflow = navigation::run(limited.as_mut(), &[Command::Down(i32::MAX), Command::Down(1)]).unwrap_err();
+    assert_eq!(overflow.step, 2);
+
+    assert!(registry.create("aim:3").is_err());
+    assert!(registry.create("drift").is_err());
+    assert!(registry.create("sideways").is_err());
+}
+
+#[test]
+fn test_plan_shortest() {
+    let plan = |h, d| plan::plan(h, d, &Objective::Count, &AimLimits::default()).map(|c| c.len());
+
+    assert_eq!(plan(0, 0), Ok(0));
+    assert_eq!(plan(15, 0), Ok(1));
+    assert_eq!(plan(15, 60), Ok(2));
+    assert_eq!(plan(15, 7), Ok(3));
+    assert_eq!(plan(15, -7), Ok(3));
+    assert_eq!(plan(10, 23), Ok(3));
+    assert_eq!(plan(0, 5), Err(PlanError::NoTravel));
+    assert_eq!(plan(-1, 0), Err(PlanError::Backwards));
+}
+
+#[test]
+fn test_plan_magnitude_and_limits() {
+    let limits  = AimLimits { min: None, max: Some(3) };
+    let planned = plan::plan(10, 23, &Objective::Magnitude, &limits).unwrap();
+
+    assert_eq!(dive2(&planned), Ok((10, 23)));
+    assert_eq!(plan::magnitude(&planned), 13);
+    assert_eq!(plan::plan(10, 23, &Objective::Count, &limits).map(|c| c.len()), Ok(4));
+
+    let by_count = plan::plan(15, 7, &Objective::Count, &AimLimits::default()).unwrap();
+    assert_eq!(by_count, vec![Command::Forward(8), Command::Down(1), Command::Forward(7)]);
+
+    let by_magnitude = plan::plan(15, 7, &Objective::Magnitude, &AimLimits::default()).unwrap();
+    assert_eq!(plan::magnitude(&by_magnitude), 16);
+
+    let limits = AimLimits { min: None, max: Some(2) };
+    assert_eq!(plan::plan(10, 23, &Objective::Count, &limits), Err(PlanError::AimAboveMax { needed: 3, max: 2 }));
+
+    // Aim 1 is allowed, but staying at aim 1 or more goes deeper than 7
+    let limits = AimLimits { min: Some(1), max: None };
+    assert_eq!(plan::plan(15, 7, &Objective::Count, &limits), Err(PlanError::AimBelowMin { needed: 0, min: 1 }));
+    let limits = AimLimits { min: Some(-1), max: None };
+    assert_eq!(plan::plan(10, -23, &Objective::Count, &limits), Err(PlanError::AimBelowMin { needed: -3, min: -1 }));
+
+    let count = &Objective::Count;
+    assert_eq!(plan::plan(1, i32::MIN, count, &AimLimits::default()), Err(PlanError::TooLarge));
+    assert_eq!(plan::plan(1, i32::MAX, count, &AimLimits::default()), Ok(vec![Command::Down(i32::MAX), Command::Forward(1)]));
+}
+
+#[test]
+fn test_steering() {
+    let commands = script::parse("down 2\nforward 3\nleft 90\nforward 4\nright 270\nback 1\n").unwrap();
+    let pose = heading::steer(&commands).unwrap();
+
+    assert!((pose.x - 4.0).abs() < 1e-9);
+    assert!((pose.y - 4.0).abs() < 1e-9);
+    assert_eq!(pose.depth, 12);
+    assert_eq!(pose.heading, 180);
+
+    // Turning does not change anything for the one-dimensional models
+    assert_eq!(dive2(&commands), Ok((6, 12)));
+}
+
+#[test]
+fn test_envelope() {
+    let commands = script::parse("up 2\nforward 3\ndown 5\nforward 4\n").unwrap();
+    let envelope = Envelope {
+        depth_min: 0,
+        depth_max: Some(5),
+        aim: AimLimits { min: Some(-1), max: None },
+    };
+
+    let violations = envelope::check(&mut Aimed::default(), &commands, &envelope).unwrap();
+    let found: Vec<(usize, &Breach)> = violations.iter().map(|v| (v.step, &v.breach)).collect();
+
+    assert_eq!(found, vec![(1, &Breach::AimTooLow(-1)),
+                           (2, &Breach::TooShallow(0)),
+                           (2, &Breach::AimTooLow(-1)),
+                           (3, &Breach::TooShallow(0)),
+                           (4, &Breach::TooDeep(5))]);
+    assert_eq!(violations[1].state, State { horizontal: 3, depth: -6, aim: -2 });
+
+    assert!(envelope::check(&mut Plain::default(), &commands, &Envelope::default()).unwrap().len() == 2);
+}
+
+fn trace(format: TraceFormat, every: usize, text: &str) -> String {
+    let commands = script::parse(text).unwrap();
+    let mut out  = Vec::new();
+
+    let mut writer = TraceWriter::new(&mut out, format).unwrap();
+    writer.trace("plain", &mut Plain::default(), &commands, every).unwrap();
+    writer.trace("aim", &mut Aimed::default(), &commands, every).unwrap();
+    writer.finish().unwrap();
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_trace_csv() {
+    let csv = trace(TraceFormat::Csv, 1, SAMPLE);
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 13);
+    assert_eq!(lines[0], "model,step,command,horizontal,depth,aim");
+    assert_eq!(lines[1], "plain,1,forward 5,5,0,0");
+    assert_eq!(lines[6], "plain,6,forward 2,15,10,0");
+    assert_eq!(lines[3], "plain,3,forward 8,13,5,0");
+    assert_eq!(lines[9], "aim,3,forward 8,13,40,5");
+    assert_eq!(lines[12], "aim,6,forward 2,15,60,10");
+}
+
+#[test]
+fn test_trace_json() {
+    let json = trace(TraceFormat::Json, 1, "forward 5\ndown 2\n");
+
+    assert_eq!(json, "[\n\
+        \x20 {\"model\": \"plain\", \"step\": 1, \"command\": \"forward 5\", \"horizontal\": 5, \"depth\": 0, \"aim\": 0},\n\
+        \x20 {\"model\": \"plain\", \"step\": 2, \"command\": \"down 2\", \"horizontal\": 5, \"depth\": 2, \"aim\": 0},\n\
+        \x20 {\"model\": \"aim\", \"step\": 1, \"command\": \"forward 5\", \"horizontal\": 5, \"depth\": 0, \"aim\": 0},\n\
+        \x20 {\"model\": \"aim\", \"step\": 2, \"command\": \"down 2\", \"horizontal\": 5, \"depth\": 0, \"aim\": 2}\n\
+        ]\n");
+
+    // An empty route still gives a valid, empty array
+    assert_eq!(trace(TraceFormat::Json, 1, ""), "[\n]\n");
+}
+
+#[test]
+fn test_trace_every() {
+    let csv = trace(TraceFormat::Csv, 4, SAMPLE);
+    let steps: Vec<(&str, &str)> = csv
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            (fields[0], fields[1])
+        })
+        .collect();
+
+    // Every fourth step, and the last one even though 6 is not a multiple
+    assert_eq!(steps, vec![("plain", "4"), ("plain", "6"), ("aim", "4"), ("aim", "6")]);
+}
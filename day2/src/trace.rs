@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use crate::Command;
+use crate::navigation::{self, NavigationModel, State};
+
+pub enum TraceFormat {
+    Csv,
+    Json,
+}
+
+pub struct TraceWriter<W: Write> {
+    out: W,
+    format: TraceFormat,
+    rows: usize,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(mut out: W, format: TraceFormat) -> io::Result<Self> {
+        match format {
+            TraceFormat::Csv => writeln!(out, "model,step,command,horizontal,depth,aim")?,
+            TraceFormat::Json => write!(out, "[")?,
+        }
+
+        Ok(Self { out, format, rows: 0 })
+    }
+
+    fn write_step(&mut self, model: &str, step: usize, command: &Command, state: &State) -> io::Result<()> {
+        match self.format {
+            TraceFormat::Csv => {
+                writeln!(self.out, "{},{},{},{},{},{}",
+                         model, step, command, state.horizontal, state.depth, state.aim)?;
+            },
+            TraceFormat::Json => {
+                if self.rows > 0 {
+                    write!(self.out, ",")?;
+                }
+                write!(self.out, "\n  {{\"model\": \"{}\", \"step\": {}, \"command\": \"{}\", \
+                                  \"horizontal\": {}, \"depth\": {}, \"aim\": {}}}",
+                       model, step, command, state.horizontal, state.depth, state.aim)?;
+            },
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    // Runs the commands through the model, writing the state after every
+    // `every`th command and always after the last one
+    pub fn trace(&mut self, name: &str, model: &mut dyn NavigationModel, commands: &[Command], every: usize)
+        -> io::Result<State>
+    {
+        for (i, command) in commands.iter().enumerate() {
+            let step  = i + 1;
+            let state = navigation::step(model, step, command)
+                .map_err(|e| io::Error::other(format!("{}: {}", name, e)))?;
+
+            if step.is_multiple_of(every) || step == commands.len() {
+                self.write_step(name, step, command, &state)?;
+            }
+        }
+
+        Ok(model.state())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if let TraceFormat::Json = self.format {
+            writeln!(self.out, "\n]")?;
+        }
+
+        self.out.flush()
+    }
+}
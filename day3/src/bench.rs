@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use crate::{TiePolicy, life_support, power_consumption};
+use crate::report::{Alphabet, Report};
+
+// The original implementation, which walks the report one character at a
+// time and clones the candidates, kept around as the baseline
+fn naive_power_consumption(report: &[Vec<char>]) -> (i64, i64) {
+    let nbits = report[0].len();
+    let mut counts: Vec<i64> = vec![0; nbits];
+
+    let mut gamma   = 0;
+    let mut epsilon = 0;
+
+    for line in report.iter() {
+        for (count, &c) in counts.iter_mut().zip(line.iter()) {
+            if c == '1' {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+
+    for (i, &count) in counts.iter().enumerate() {
+        if count > 0 {
+            gamma |= 1 << (nbits - i - 1);
+        } else {
+            epsilon |= 1 << (nbits - i - 1);
+        }
+    }
+
+    (gamma, epsilon)
+}
+
+// Reads the lines the way the original did, both as characters and as values
+fn naive_parse(lines: &[String]) -> (Vec<Vec<char>>, Vec<i64>) {
+    let chars: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+    let values = lines.iter().map(|line| i64::from_str_radix(line, 2).unwrap()).collect();
+
+    (chars, values)
+}
+
+fn naive_life_support(digits: &[i64], nbits: usize) -> (i64, i64) {
+    let count_bits = |acc, n: &i64, bit: usize| if (n >> bit) & 1 != 0 { acc + 1 } else { acc - 1 };
+
+    let mut o2_vec  = digits.to_vec();
+    let mut co2_vec = digits.to_vec();
+
+    for bit in (0..nbits).rev() {
+        if o2_vec.len() > 1 {
+            let counts = o2_vec.iter().fold(0, |acc, n| count_bits(acc, n, bit));
+            let target = (counts >= 0) as i64;
+            o2_vec.retain(|n| (n >> bit) & 1 == target);
+        }
+
+        if co2_vec.len() > 1 {
+            let counts = co2_vec.iter().fold(0, |acc, n| count_bits(acc, n, bit));
+            let target = (counts < 0) as i64;
+            co2_vec.retain(|n| (n >> bit) & 1 == target);
+        }
+    }
+
+    (o2_vec[0], co2_vec[0])
+}
+
+// xorshift64*, so that the same seed always gives the same report
+fn generate(rows: usize, width: usize, mut seed: u64) -> Vec<String> {
+    let mut next = move || {
+        seed ^= seed >> 12;
+        seed ^= seed << 25;
+        seed ^= seed >> 27;
+        seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    };
+
+    (0..rows)
+        .map(|_| (0..width).map(|_| if next() >> 63 == 1 { '1' } else { '0' }).collect())
+        .collect()
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start  = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+// Both sides are timed from the text lines on, reading them included, so
+// that the end to end row compares like with like
+pub fn run(rows: usize, width: usize) {
+    let (lines, generated) = time(|| generate(rows, width, 0x5eed));
+    println!("Generated {} lines of {} bits in {:.2?}", rows, width, generated);
+
+    let ties = TiePolicy::default();
+
+    let (report, packed_parse) = time(|| Report::new(&lines, Alphabet::binary()).unwrap());
+    let ((g, e), packed_power) = time(|| power_consumption(&report, &ties));
+    let ((o2, co2), packed_life) = time(|| life_support(&report, &ties));
+    let packed_total = packed_parse + packed_power + packed_life;
+    println!("Packed {} lines in {:.2?}", report.len(), packed_parse);
+
+    // The baseline only works while the values fit into a machine word
+    if width > 62 {
+        println!("{:<20} {:>12}", "", "packed");
+        println!("{:<20} {:>12.2?}", "Reading", packed_parse);
+        println!("{:<20} {:>12.2?}", "Power consumption", packed_power);
+        println!("{:<20} {:>12.2?}", "Life support", packed_life);
+        println!("{:<20} {:>12.2?}", "End to end", packed_total);
+        return;
+    }
+
+    let ((chars, values), naive_parse) = time(|| naive_parse(&lines));
+    let ((ng, ne), naive_power) = time(|| naive_power_consumption(&chars));
+    let ((no2, nco2), naive_life) = time(|| naive_life_support(&values, width));
+    let naive_total = naive_parse + naive_power + naive_life;
+
+    let speedup = |naive: Duration, packed: Duration| naive.as_secs_f64() / packed.as_secs_f64();
+    let row = |name: &str, naive: Duration, packed: Duration| {
+        println!("{:<20} {:>12.2?} {:>12.2?} {:>8.1}x", name, naive, packed, speedup(naive, packed));
+    };
+
+    println!("{:<20} {:>12} {:>12} {:>9}", "", "naive", "packed", "speedup");
+    row("Reading", naive_parse, packed_parse);
+    row("Power consumption", naive_power, packed_power);
+    row("Life support", naive_life, packed_life);
+    row("End to end", naive_total, packed_total);
+
+    let same = [(g, ng), (e, ne), (o2, no2), (co2, nco2)]
+        .iter()
+        .all(|(packed, naive)| packed.to_string() == naive.to_string());
+    println!("Results {}", if same { "agree" } else { "DIFFER" });
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::ops::Mul;
+
+// Unsigned integer of any size, stored as little-endian 64-bit limbs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    // Digits are given most significant first
+    pub fn from_digits(digits: impl IntoIterator<Item = u32>, radix: u32) -> Self {
+        let mut n = Self::zero();
+
+        for digit in digits {
+            n.mul_add_small(radix as u64, digit as u64);
+        }
+
+        n
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    // self = self * m + a
+    fn mul_add_small(&mut self, m: u64, a: u64) {
+        let mut carry = a as u128;
+
+        for limb in self.limbs.iter_mut() {
+            let v = *limb as u128 * m as u128 + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+
+        if carry > 0 {
+            self.limbs.push(carry as u64);
+        }
+
+        self.normalize();
+    }
+
+    // self = self / d, returning the remainder
+    fn div_small(&mut self, d: u64) -> u64 {
+        let mut rem = 0u128;
+
+        for limb in self.limbs.iter_mut().rev() {
+            let v = (rem << 64) | *limb as u128;
+            *limb = (v / d as u128) as u64;
+            rem = v % d as u128;
+        }
+
+        self.normalize();
+        rem as u64
+    }
+
+    // Renders the number in the given radix, using `symbols` for the digits
+    // and padding it with the zero symbol to at least `width` digits
+    pub fn to_digits(&self, symbols: &[char], width: usize) -> String {
+        let radix = symbols.len() as u64;
+        let mut n = self.clone();
+        let mut digits = Vec::new();
+
+        while !n.is_zero() {
+            digits.push(symbols[n.div_small(radix) as usize]);
+        }
+
+        while digits.len() < width.max(1) {
+            digits.push(symbols[0]);
+        }
+
+        digits.iter().rev().collect()
+    }
+
+    pub fn to_binary(&self, width: usize) -> String {
+        self.to_digits(&['0', '1'], width)
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len()];
+
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u128;
+
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let v = a as u128 * b as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = v as u64;
+                carry = v >> 64;
+            }
+
+            limbs[i + other.limbs.len()] = carry as u64;
+        }
+
+        let mut product = BigUint { limbs };
+        product.normalize();
+        product
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+
+        loop {
+            chunks.push(n.div_small(CHUNK));
+            if n.is_zero() {
+                break;
+            }
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:019}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::report::Report;
+
+pub struct Step {
+    pub column: usize,
+    // Indexed by digit, among the candidates left before this step
+    pub counts: Vec<usize>,
+    pub target: u8,
+    pub removed: usize,
+    pub left: usize,
+}
+
+pub struct MaskStep {
+    pub pattern: String,
+    pub removed: usize,
+    pub left: usize,
+}
+
+pub struct Elimination {
+    pub steps: Vec<Step>,
+    pub masks: Vec<MaskStep>,
+    pub survivor: usize,
+}
+
+impl Elimination {
+    // The symbols that were kept at each step
+    pub fn path(&self, report: &Report) -> String {
+        let symbols = report.alphabet().symbols();
+
+        self.steps
+            .iter()
+            .map(|step| symbols[step.target as usize].to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn counts(report: &Report, step: &Step) -> String {
+    let symbols = report.alphabet().symbols();
+
+    step.counts
+        .iter()
+        .enumerate()
+        .map(|(d, n)| format!("{}:{}", symbols[d], n))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn describe(report: &Report, step: Option<&Step>, counts_width: usize) -> String {
+    let symbols = report.alphabet().symbols();
+
+    match step {
+        Some(step) => format!("{:<counts_width$} keep {}, removed {:>5}, {:>5} left",
+                              counts(report, step), symbols[step.target as usize], step.removed, step.left),
+        None => "-".to_string(),
+    }
+}
+
+// `line_numbers` gives the file line of every report row
+pub fn print(report: &Report, line_numbers: &[usize], ratings: &[(&str, &Elimination)]) {
+    let counts_width = ratings
+        .iter()
+        .flat_map(|(_, e)| e.steps.iter())
+        .map(|step| counts(report, step).len())
+        .max()
+        .unwrap_or(0);
+
+    let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+    for column in 0..report.width() {
+        let steps: Vec<Option<&Step>> = ratings
+            .iter()
+            .map(|(_, e)| e.steps.iter().find(|step| step.column == column))
+            .collect();
+
+        // Once every rating is down to one line there is nothing left to show
+        if steps.iter().all(|step| step.is_none()) {
+            break;
+        }
+
+        rows.push((column, steps.into_iter().map(|step| describe(report, step, counts_width)).collect()));
+    }
+
+    // Every rating column is as wide as its name or its widest cell
+    let widths: Vec<usize> = ratings
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| rows.iter().map(|(_, cells)| cells[i].len()).fold(name.len(), usize::max))
+        .collect();
+
+    let mut header = format!("{:>4}", "bit");
+    for ((name, _), width) in ratings.iter().zip(widths.iter()) {
+        header += &format!(" | {:<width$}", name);
+    }
+    println!("{}", header.trim_end());
+
+    for (column, cells) in rows.iter() {
+        let mut line = format!("{:>4}", column);
+        for (cell, width) in cells.iter().zip(widths.iter()) {
+            line += &format!(" | {:<width$}", cell);
+        }
+        println!("{}", line.trim_end());
+    }
+
+    for (name, elimination) in ratings.iter() {
+        for mask in elimination.masks.iter() {
+            println!("{}: mask {} removed {}, {} left", name, mask.pattern, mask.removed, mask.left);
+        }
+    }
+
+    for (name, elimination) in ratings.iter() {
+        let value = report.value(elimination.survivor);
+        let symbols = report.alphabet().symbols();
+
+        println!("{}: line {} ({}) = {}, path {}",
+                 name, line_numbers[elimination.survivor], value.to_digits(symbols, report.width()), value,
+                 elimination.path(report));
+    }
+}
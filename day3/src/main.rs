@@ -1,98 +1,250 @@
+#[cfg(test)]
+mod tests;
+mod bench;
+mod bignum;
+mod explain;
+mod pipeline;
+mod report;
+mod validate;
+
+use std::collections::HashSet;
 use std::env;
-use std::io::{self, BufReader, prelude::*};
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, prelude::*};
 use std::fs::File;
+use std::process;
 
-fn read_input(fname: &str) -> io::Result<Vec<Vec<char>>> {
+use crate::bignum::BigUint;
+use crate::explain::Elimination;
+use crate::pipeline::Pipeline;
+use crate::report::{Alphabet, Report, ReportError, Selection, Tie};
+use crate::validate::Diagnostic;
+
+fn read_input(fname: &str) -> io::Result<Vec<String>> {
     let f = File::open(fname)?;
     let reader = BufReader::new(f);
 
-    let mut report = Vec::new();
+    reader.lines().collect()
+}
+
+// Which symbol wins a tie, for each of the four ratings
+pub struct TiePolicy {
+    pub gamma: Tie,
+    pub epsilon: Tie,
+    pub o2: Tie,
+    pub co2: Tie,
+}
 
-    for line in reader.lines() {
-        report.push(line?.chars().collect());
+impl Default for TiePolicy {
+    fn default() -> Self {
+        Self { gamma: Tie::Low, epsilon: Tie::High, o2: Tie::High, co2: Tie::Low }
     }
+}
+
+impl TiePolicy {
+    // Accepts "o2=high,co2=first" style lists
+    fn update(&mut self, spec: &str) -> Result<(), String> {
+        for item in spec.split(',') {
+            let (rating, tie) = item
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' is not of the form RATING=POLICY", item))?;
+            let tie = tie.parse()?;
+
+            match rating {
+                "gamma" => self.gamma = tie,
+                "epsilon" => self.epsilon = tie,
+                "o2" => self.o2 = tie,
+                "co2" => self.co2 = tie,
+                _ => return Err(format!("unknown rating '{}'", rating)),
+            }
+        }
+
+        Ok(())
+    }
+}
 
-    Ok(report)
+struct Options {
+    fname: Option<String>,
+    alphabet: Alphabet,
+    ties: TiePolicy,
+    bench: Option<usize>,
+    width: usize,
+    explain: bool,
+    strict: bool,
+    ratings: Vec<String>,
 }
 
-fn power_consumption(report: &Vec<Vec<char>>) -> (i32, i32) {
-    let nbits = report[0].len();
-    let mut counts: Vec<i32> = vec![0; nbits];
+fn usage() -> ! {
+    eprintln!("Usage: day3 <input> [--alphabet SYMBOLS] [--tie RATING=high|low|first,...] [--explain]");
+    eprintln!("                   [--strict] [--ratings 'NAME = CRITERION, ...; ...']...");
+    eprintln!("       CRITERION is most[:N][@TIE], least[:N][@TIE] or mask:PATTERN ('?' matches anything)");
+    eprintln!("       RATING is one of gamma, epsilon, o2, co2");
+    eprintln!("       day3 --bench LINES [--width BITS]");
+    process::exit(1);
+}
 
-    let mut gamma   = 0;
-    let mut epsilon = 0;
+fn fail(e: impl fmt::Display) -> ! {
+    eprintln!("Error: {}", e);
+    process::exit(1);
+}
 
-    for line in report.iter() {
-        for i in 0..nbits {
-            if line[i] == '1' {
-                counts[i] += 1;
-            } else {
-                counts[i] -= 1;
-            }
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+
+    let mut fname    = None;
+    let mut alphabet = Alphabet::binary();
+    let mut ties     = TiePolicy::default();
+    let mut bench    = None;
+    let mut width    = 12;
+    let mut explain  = false;
+    let mut strict   = false;
+    let mut ratings  = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--alphabet" => {
+                alphabet = Alphabet::new(&args.next().unwrap_or_else(|| usage())).unwrap_or_else(|e| fail(e));
+            },
+            "--tie" => ties.update(&args.next().unwrap_or_else(|| usage())).unwrap_or_else(|e| fail(e)),
+            "--explain" => explain = true,
+            "--strict" => strict = true,
+            "--ratings" => ratings.push(args.next().unwrap_or_else(|| usage())),
+            "--bench" => bench = Some(args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())),
+            "--width" => width = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage()),
+            _ if fname.is_none() => fname = Some(arg),
+            _ => usage(),
         }
     }
 
-    for i in 0..nbits {
-        if counts[i] > 0 {
-            gamma |= 1 << (nbits - i - 1);
-        } else {
-            epsilon |= 1 << (nbits - i - 1);
-        }
+    if fname.is_none() && bench.is_none() || bench == Some(0) || width == 0 {
+        usage();
     }
 
-    (gamma, epsilon)
+    Options { fname, alphabet, ties, bench, width, explain, strict, ratings }
+}
+
+fn power_consumption(report: &Report, ties: &TiePolicy) -> (BigUint, BigUint) {
+    let rows  = report.all_rows();
+    let radix = report.alphabet().radix() as u32;
+
+    let rating = |selection, tie| {
+        let digits = (0..report.width()).map(|column| report.select(&rows, column, selection, tie, true) as u32);
+        BigUint::from_digits(digits, radix)
+    };
+
+    (rating(Selection::MostCommon, ties.gamma), rating(Selection::LeastCommon, ties.epsilon))
+}
+
+fn life_support_steps(report: &Report, ties: &TiePolicy) -> (Elimination, Elimination) {
+    // Selecting only symbols that appear always leaves at least one line
+    (Pipeline::o2(ties.o2).run(report).unwrap(),
+     Pipeline::co2(ties.co2).run(report).unwrap())
 }
 
-fn life_support(report: &Vec<Vec<char>>) -> (i32, i32) {
-    let nbits = report[0].len();
+fn life_support(report: &Report, ties: &TiePolicy) -> (BigUint, BigUint) {
+    let (o2, co2) = life_support_steps(report, ties);
 
-    let digits: Vec<i32> = report
+    (report.value(o2.survivor), report.value(co2.survivor))
+}
+
+// Leaves out the lines that cannot be read, along with the file line number
+// of every line that is kept, since report rows no longer match them
+fn usable_lines(lines: Vec<String>, diagnostics: &[Diagnostic]) -> (Vec<String>, Vec<usize>) {
+    let unusable: HashSet<usize> = diagnostics
         .iter()
-        .map(|line| i32::from_str_radix(&line.iter().cloned().collect::<String>(), 2).unwrap())
+        .filter(|d| d.is_fatal() && d.line > 0)
+        .map(|d| d.line)
         .collect();
 
-    let mut current_bit = nbits - 1;
+    lines
+        .into_iter()
+        .zip(1..)
+        .filter(|(_, line)| !unusable.contains(line))
+        .unzip()
+}
 
-    let count_bits = |acc, n: &i32, bit| {
-        if n & (1 << bit) != 0 {
-            acc + 1
-        } else {
-            acc - 1
-        }
-    };
+fn main() {
+    let options = parse_args();
 
-    let counts = digits.iter().fold(0, |acc, n| count_bits(acc, n, current_bit));
-    let target = (counts >= 0) as i32;
-    let (mut o2_vec, mut co2_vec): (Vec<i32>, Vec<i32>)
-        = digits.iter()
-                .partition(|&n| (n & 1 << current_bit) >> current_bit == target);
+    if let Some(rows) = options.bench {
+        bench::run(rows, options.width);
+        return;
+    }
 
-    while o2_vec.len() > 1 || co2_vec.len() > 1 {
-        current_bit -= 1;
+    let fname = options.fname.unwrap_or_default();
+    let lines = read_input(&fname).unwrap_or_else(|e| fail(e));
 
-        if o2_vec.len() > 1 {
-            let counts = o2_vec.iter().fold(0, |acc, n| count_bits(acc, n, current_bit));
-            let target = (counts >= 0) as i32;
-            o2_vec.retain(|n| (n & 1 << current_bit) >> current_bit == target);
+    let diagnostics = validate::validate(&lines, &options.alphabet);
+    {
+        // A bad report can produce millions of diagnostics, so do not lock
+        // and flush stderr once per line
+        let mut stderr = BufWriter::new(io::stderr().lock());
+        for diagnostic in diagnostics.iter() {
+            let _ = writeln!(stderr, "{}: {}", fname, diagnostic);
         }
+    }
+
+    // Duplicates never stop the ratings, identical lines have identical values
+    let fatal = diagnostics.iter().filter(|d| d.is_fatal()).count();
+    if (options.strict || lines.is_empty()) && fatal > 0 {
+        fail(format_args!("{} problem(s) in {}, refusing to compute ratings", fatal, fname));
+    }
+
+    let (lines, line_numbers) = usable_lines(lines, &diagnostics);
+    let report = Report::new(&lines, options.alphabet).unwrap_or_else(|e| {
+        let line = line_numbers.get(e.line - 1).copied().unwrap_or(e.line);
+        fail(format_args!("{}: {}", fname, ReportError { line, ..e }))
+    });
 
-        if co2_vec.len() > 1 {
-            let counts = co2_vec.iter().fold(0, |acc, n| count_bits(acc, n, current_bit));
-            let target = (counts < 0) as i32;
-            co2_vec.retain(|n| (n & 1 << current_bit) >> current_bit == target);
+    let nbits   = report.width();
+    let symbols = report.alphabet().symbols();
+
+    // Binary reports keep the familiar 0b prefix, other alphabets are named
+    let (prefix, suffix) = if symbols == ['0', '1'] {
+        ("0b".to_string(), String::new())
+    } else {
+        (String::new(), format!(" (base {})", symbols.len()))
+    };
+    let digits = |n: &BigUint, width| format!("{}{}", prefix, n.to_digits(symbols, width));
+
+    let (g, e) = power_consumption(&report, &options.ties);
+    let power  = &g * &e;
+    println!("Gamma = {}, epsilon = {} -> Result: {}", g, e, power);
+    println!("Gamma = {}, epsilon = {} -> Result: {}{}",
+             digits(&g, nbits), digits(&e, nbits), digits(&power, 0), suffix);
+
+    let mut pipelines = Vec::new();
+    for config in options.ratings.iter() {
+        pipelines.extend(Pipeline::parse_all(config, report.alphabet()).unwrap_or_else(|e| fail(e)));
+    }
+
+    let mut custom = Vec::new();
+    for pipeline in pipelines.iter() {
+        match pipeline.run(&report) {
+            Ok(elimination) => custom.push((pipeline.name.as_str(), elimination)),
+            Err(e) => eprintln!("{}: {}", fname, e),
         }
     }
 
-    (o2_vec[0], co2_vec[0])
-}
+    let (o2_steps, co2_steps) = life_support_steps(&report, &options.ties);
+    if options.explain {
+        let mut ratings = vec![("O2", &o2_steps), ("CO2", &co2_steps)];
+        ratings.extend(custom.iter().map(|(name, elimination)| (*name, elimination)));
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let report = read_input(&args[1]).unwrap();
+        println!();
+        explain::print(&report, &line_numbers, &ratings);
+        println!();
+    }
 
-    let (g, e) = power_consumption(&report);
-    println!("Gamma = {}, epsilon = {} -> Result: {}", g, e, g * e);
+    let (o2, co2) = (report.value(o2_steps.survivor), report.value(co2_steps.survivor));
+    let rating = &o2 * &co2;
+    println!("O2 = {}, CO2 = {} -> Result: {}", o2, co2, rating);
+    println!("O2 = {}, CO2 = {} -> Result: {}{}",
+             digits(&o2, nbits), digits(&co2, nbits), digits(&rating, 0), suffix);
 
-    let (o2, co2) = life_support(&report);
-    println!("O2 = {}, CO2 = {} -> Result: {}", o2, co2, o2 * co2);
+    for (name, elimination) in custom.iter() {
+        let value = report.value(elimination.survivor);
+        println!("{} = {} ({}{}, line {})", name, value, digits(&value, nbits), suffix,
+                 line_numbers[elimination.survivor]);
+    }
 }
@@ -0,0 +1,147 @@
+use crate::explain::{Elimination, MaskStep, Step};
+use crate::report::{Alphabet, Report, Selection, Tie};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Criterion {
+    // Applies to the next `columns` columns, or to all remaining ones
+    Select { selection: Selection, columns: Option<usize>, tie: Tie },
+    // Keeps the lines matching the pattern, `None` matches any symbol
+    Mask { pattern: String, digits: Vec<Option<u8>> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub name: String,
+    pub criteria: Vec<Criterion>,
+}
+
+fn parse_criterion(spec: &str, alphabet: &Alphabet) -> Result<Criterion, String> {
+    if let Some(pattern) = spec.strip_prefix("mask:") {
+        let digits = pattern
+            .chars()
+            .map(|c| match c {
+                '?' => Ok(None),
+                _ => alphabet.digit(c).map(Some).ok_or_else(|| format!("'{}' in mask '{}' is not in the alphabet", c, pattern)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        return Ok(Criterion::Mask { pattern: pattern.to_string(), digits });
+    }
+
+    let (rest, tie) = match spec.split_once('@') {
+        Some((rest, tie)) => (rest, Some(tie.parse()?)),
+        None => (spec, None),
+    };
+
+    let (selection, columns) = match rest.split_once(':') {
+        Some((selection, columns)) => (selection, columns),
+        None => (rest, "*"),
+    };
+
+    let (selection, default_tie) = match selection {
+        "most" => (Selection::MostCommon, Tie::High),
+        "least" => (Selection::LeastCommon, Tie::Low),
+        _ => return Err(format!("unknown criterion '{}'", spec)),
+    };
+
+    let columns = match columns {
+        "*" => None,
+        n => Some(n.parse().map_err(|_| format!("'{}' is not a number of columns", n))?),
+    };
+
+    Ok(Criterion::Select { selection, columns, tie: tie.unwrap_or(default_tie) })
+}
+
+impl Pipeline {
+    pub fn o2(tie: Tie) -> Self {
+        let criteria = vec![Criterion::Select { selection: Selection::MostCommon, columns: None, tie }];
+        Self { name: "O2".to_string(), criteria }
+    }
+
+    pub fn co2(tie: Tie) -> Self {
+        let criteria = vec![Criterion::Select { selection: Selection::LeastCommon, columns: None, tie }];
+        Self { name: "CO2".to_string(), criteria }
+    }
+
+    // Ratings are separated by semicolons, each one looking like
+    // "name = most:4, least@first, mask:1??0"
+    pub fn parse_all(config: &str, alphabet: &Alphabet) -> Result<Vec<Self>, String> {
+        let mut pipelines = Vec::new();
+
+        for rating in config.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+            let (name, criteria) = rating
+                .split_once('=')
+                .ok_or_else(|| format!("rating '{}' is not of the form NAME = CRITERIA", rating))?;
+
+            let criteria = criteria
+                .split(',')
+                .map(|c| parse_criterion(c.trim(), alphabet))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            pipelines.push(Pipeline { name: name.trim().to_string(), criteria });
+        }
+
+        Ok(pipelines)
+    }
+
+    pub fn run(&self, report: &Report) -> Result<Elimination, String> {
+        let mut candidates = report.all_rows();
+        let mut column = 0;
+        let mut steps  = Vec::new();
+        let mut masks  = Vec::new();
+
+        for criterion in self.criteria.iter() {
+            match criterion {
+                Criterion::Select { selection, columns, tie } => {
+                    let end = columns.map_or(report.width(), |n| (column + n).min(report.width()));
+
+                    while column < end {
+                        let before = candidates.len();
+                        if before <= 1 {
+                            break;
+                        }
+
+                        let counts = report.counts(&candidates, column);
+                        let target = report.select(&candidates, column, *selection, *tie, false);
+                        candidates.intersect(report.rows_with(column, target));
+
+                        let left = candidates.len();
+                        steps.push(Step { column, counts, target, removed: before - left, left });
+                        column += 1;
+                    }
+
+                    column = end;
+                },
+                Criterion::Mask { pattern, digits } => {
+                    if digits.len() != report.width() {
+                        return Err(format!("mask '{}' is {} symbols wide, the report is {}",
+                                           pattern, digits.len(), report.width()));
+                    }
+
+                    let before = candidates.len();
+                    for (c, digit) in digits.iter().enumerate() {
+                        if let Some(d) = digit {
+                            candidates.intersect(report.rows_with(c, *d));
+                        }
+                    }
+
+                    let left = candidates.len();
+                    masks.push(MaskStep { pattern: pattern.clone(), removed: before - left, left });
+                },
+            }
+        }
+
+        let survivor = match candidates.first() {
+            Some(row) => row,
+            None => return Err(format!("no line is left for rating '{}'", self.name)),
+        };
+
+        // Several identical lines are fine, they all have the same value
+        let identical = (0..report.width()).all(|c| report.counts(&candidates, c).iter().filter(|&&n| n > 0).count() == 1);
+        if !identical {
+            return Err(format!("{} different lines are left for rating '{}'", candidates.len(), self.name));
+        }
+
+        Ok(Elimination { steps, masks, survivor })
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt;
+
+use crate::bignum::BigUint;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    // Digit of every ASCII symbol, or NONE, so that reading a report does not
+    // search the alphabet for every character
+    ascii: [u8; 128],
+}
+
+const NONE: u8 = u8::MAX;
+
+impl Alphabet {
+    pub fn new(symbols: &str) -> Result<Self, String> {
+        let symbols: Vec<char> = symbols.chars().collect();
+
+        if symbols.len() < 2 {
+            return Err("an alphabet needs at least two symbols".to_string());
+        }
+
+        // Digits are stored as bytes, with one value left for "no digit"
+        if symbols.len() > NONE as usize {
+            return Err(format!("an alphabet can have at most {} symbols", NONE));
+        }
+
+        for (i, c) in symbols.iter().enumerate() {
+            if symbols[..i].contains(c) {
+                return Err(format!("symbol '{}' appears twice in the alphabet", c));
+            }
+        }
+
+        Ok(Self::from_symbols(symbols))
+    }
+
+    fn from_symbols(symbols: Vec<char>) -> Self {
+        let mut ascii = [NONE; 128];
+        for (d, &c) in symbols.iter().enumerate() {
+            if c.is_ascii() {
+                ascii[c as usize] = d as u8;
+            }
+        }
+
+        Self { symbols, ascii }
+    }
+
+    pub fn binary() -> Self {
+        Self::from_symbols(vec!['0', '1'])
+    }
+
+    pub fn radix(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn digit(&self, c: char) -> Option<u8> {
+        match self.ascii.get(c as usize) {
+            Some(&NONE) => None,
+            Some(&d) => Some(d),
+            None => self.symbols.iter().position(|&s| s == c).map(|d| d as u8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tie {
+    // Highest or lowest symbol in alphabet order
+    High,
+    Low,
+    // The symbol of the earliest line among the tied ones
+    First,
+}
+
+impl std::str::FromStr for Tie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "high" => Ok(Tie::High),
+            "low" => Ok(Tie::Low),
+            "first" => Ok(Tie::First),
+            _ => Err(format!("unknown tie policy '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    MostCommon,
+    LeastCommon,
+}
+
+// A set of row indices, one bit per row
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowSet {
+    words: Vec<u64>,
+}
+
+impl RowSet {
+    pub fn empty(rows: usize) -> Self {
+        Self { words: vec![0; rows.div_ceil(64)] }
+    }
+
+    pub fn full(rows: usize) -> Self {
+        let mut set = Self { words: vec![u64::MAX; rows.div_ceil(64)] };
+
+        if !rows.is_multiple_of(64) {
+            if let Some(last) = set.words.last_mut() {
+                *last = (1 << (rows % 64)) - 1;
+            }
+        }
+
+        set
+    }
+
+    pub fn insert(&mut self, row: usize) {
+        self.words[row / 64] |= 1 << (row % 64);
+    }
+
+    pub fn contains(&self, row: usize) -> bool {
+        self.words[row / 64] & (1 << (row % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // Number of rows in both sets, without building the intersection
+    pub fn count_common(&self, other: &RowSet) -> usize {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    pub fn first_common(&self, other: &RowSet) -> Option<usize> {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .enumerate()
+            .find(|(_, (a, b))| *a & *b != 0)
+            .map(|(i, (a, b))| i * 64 + (a & b).trailing_zeros() as usize)
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.first_common(self)
+    }
+
+    pub fn intersect(&mut self, other: &RowSet) {
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a &= b;
+        }
+    }
+}
+
+// The report is stored column by column: for every column and symbol there
+// is the set of rows that have that symbol there. Counting a symbol among
+// some candidate rows is then a popcount over the intersection.
+pub struct Report {
+    alphabet: Alphabet,
+    width: usize,
+    len: usize,
+    planes: Vec<Vec<RowSet>>,
+}
+
+#[derive(Debug)]
+pub struct ReportError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Report {
+    pub fn new(lines: &[String], alphabet: Alphabet) -> Result<Self, ReportError> {
+        let width = match lines.first() {
+            Some(line) => line.chars().count(),
+            None => return Err(ReportError { line: 1, message: "the report is empty".to_string() }),
+        };
+
+        let len = lines.len();
+        let mut planes = vec![vec![RowSet::empty(len); alphabet.radix()]; width];
+
+        for (i, line) in lines.iter().enumerate() {
+            let error = |message| ReportError { line: i + 1, message };
+
+            let mut n = 0;
+            for (column, c) in line.chars().enumerate() {
+                let digit = alphabet.digit(c).ok_or_else(|| error(format!("'{}' is not in the alphabet", c)))?;
+
+                if column < width {
+                    planes[column][digit as usize].insert(i);
+                }
+                n += 1;
+            }
+
+            if n != width {
+                return Err(error(format!("expected {} symbols, found {}", width, n)));
+            }
+        }
+
+        Ok(Self { alphabet, width, len, planes })
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn all_rows(&self) -> RowSet {
+        RowSet::full(self.len)
+    }
+
+    // The rows that have `digit` in `column`
+    pub fn rows_with(&self, column: usize, digit: u8) -> &RowSet {
+        &self.planes[column][digit as usize]
+    }
+
+    pub fn digit(&self, row: usize, column: usize) -> u8 {
+        self.planes[column].iter().position(|plane| plane.contains(row)).unwrap() as u8
+    }
+
+    pub fn value(&self, row: usize) -> BigUint {
+        let digits = (0..self.width).map(|column| self.digit(row, column) as u32);
+        BigUint::from_digits(digits, self.alphabet.radix() as u32)
+    }
+
+    // How often each symbol appears in a column among the given rows
+    pub fn counts(&self, rows: &RowSet, column: usize) -> Vec<usize> {
+        self.planes[column].iter().map(|plane| plane.count_common(rows)).collect()
+    }
+
+    // Picks the most or least common symbol of a column among the given
+    // rows. Symbols that do not appear at all only count as least common
+    // if `absent` is set.
+    pub fn select(&self, rows: &RowSet, column: usize, selection: Selection, tie: Tie, absent: bool) -> u8 {
+        let eligible: Vec<(u8, usize)> = self
+            .counts(rows, column)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, n)| absent || n > 0)
+            .map(|(d, n)| (d as u8, n))
+            .collect();
+
+        let best = match selection {
+            Selection::MostCommon => eligible.iter().map(|&(_, n)| n).max(),
+            Selection::LeastCommon => eligible.iter().map(|&(_, n)| n).min(),
+        };
+
+        let tied: Vec<u8> = eligible
+            .iter()
+            .filter(|&&(_, n)| Some(n) == best)
+            .map(|&(d, _)| d)
+            .collect();
+
+        match tie {
+            Tie::Low => tied[0],
+            Tie::High => tied[tied.len() - 1],
+            Tie::First => {
+                tied.iter()
+                    .copied()
+                    .min_by_key(|&d| self.rows_with(column, d).first_common(rows).unwrap_or(usize::MAX))
+                    .unwrap()
+            },
+        }
+    }
+}
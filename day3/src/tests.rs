@@ -0,0 +1,188 @@
+use crate::*;
+use crate::pipeline::Pipeline;
+use crate::report::RowSet;
+use crate::validate::{Diagnostic, Problem};
+
+fn report(lines: &[&str]) -> Report {
+    let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    Report::new(&lines, Alphabet::binary()).unwrap()
+}
+
+const SAMPLE: [&str; 12] = [
+    "00100", "11110", "10110", "10111", "10101", "01111",
+    "00111", "11100", "10000", "11001", "00010", "01010",
+];
+
+#[test]
+fn test_sample() {
+    let report = report(&SAMPLE);
+
+    let (g, e) = power_consumption(&report, &TiePolicy::default());
+    assert_eq!((g.to_string(), e.to_string()), ("22".to_string(), "9".to_string()));
+
+    let (o2, co2) = life_support(&report, &TiePolicy::default());
+    assert_eq!((&o2 * &co2).to_string(), "230");
+}
+
+#[test]
+fn test_wide_report() {
+    let ones = "1".repeat(128);
+    let mixed = format!("{}{}", "0".repeat(64), "1".repeat(64));
+    let report = report(&[&ones, &ones, &mixed]);
+
+    let (g, e) = power_consumption(&report, &TiePolicy::default());
+    assert_eq!(g.to_string(), u128::MAX.to_string());
+    assert!(e.is_zero());
+
+    let (o2, co2) = life_support(&report, &TiePolicy::default());
+    assert_eq!(o2.to_binary(128), ones);
+    assert_eq!(co2.to_string(), u64::MAX.to_string());
+    assert_eq!((&o2 * &co2).to_string(), "6277101735386680763495507056286727952620534092958556749825");
+}
+
+#[test]
+fn test_bignum_digits() {
+    let n = BigUint::from_digits("123456789012345678901234567890".chars().map(|c| c.to_digit(10).unwrap()), 10);
+    assert_eq!(n.to_string(), "123456789012345678901234567890");
+    assert_eq!(BigUint::from_digits([1, 0, 1], 2).to_binary(5), "00101");
+    assert_eq!(BigUint::zero().to_string(), "0");
+}
+
+#[test]
+fn test_tie_policies() {
+    let report = report(&["10", "01", "11", "00"]);
+
+    let (o2, co2) = life_support(&report, &TiePolicy::default());
+    assert_eq!((o2.to_binary(2), co2.to_binary(2)), ("11".to_string(), "00".to_string()));
+
+    let mut ties = TiePolicy::default();
+    ties.update("o2=low,co2=first").unwrap();
+    let (o2, co2) = life_support(&report, &ties);
+    assert_eq!((o2.to_binary(2), co2.to_binary(2)), ("00".to_string(), "10".to_string()));
+
+    assert!(ties.update("o2=maybe").is_err());
+    assert!(ties.update("n2=low").is_err());
+}
+
+#[test]
+fn test_other_alphabets() {
+    let lines: Vec<String> = ["0123", "3210", "1133", "1200", "1033"].iter().map(|l| l.to_string()).collect();
+    let report = Report::new(&lines, Alphabet::new("0123").unwrap()).unwrap();
+
+    let (g, e) = power_consumption(&report, &TiePolicy::default());
+    assert_eq!(g.to_digits(&['0', '1', '2', '3'], 4), "1133");
+    assert_eq!(e.to_digits(&['0', '1', '2', '3'], 4), "2322");
+
+    let (o2, co2) = life_support(&report, &TiePolicy::default());
+    assert_eq!(o2, report.value(3));
+    assert_eq!(co2, report.value(0));
+
+    assert!(Report::new(&lines, Alphabet::binary()).is_err());
+}
+
+#[test]
+fn test_row_sets() {
+    let mut set = RowSet::full(130);
+    assert_eq!(set.len(), 130);
+
+    let mut odd = RowSet::empty(130);
+    for row in (1..130).step_by(2) {
+        odd.insert(row);
+    }
+    assert_eq!(set.count_common(&odd), 65);
+
+    set.intersect(&odd);
+    assert_eq!(set.first(), Some(1));
+    assert!(set.contains(129) && !set.contains(128));
+}
+
+#[test]
+fn test_elimination_steps() {
+    let report = report(&SAMPLE);
+    let (o2, co2) = life_support_steps(&report, &TiePolicy::default());
+
+    assert_eq!(o2.steps.len(), 5);
+    assert_eq!(o2.steps[0].counts, vec![5, 7]);
+    assert_eq!((o2.steps[0].target, o2.steps[0].removed, o2.steps[0].left), (1, 5, 7));
+    assert_eq!(o2.path(&report), "1 0 1 1 1");
+    assert_eq!(o2.survivor, 3);
+
+    assert_eq!(co2.steps.len(), 3);
+    assert_eq!(co2.path(&report), "0 1 0");
+    assert_eq!(report.value(co2.survivor).to_string(), "10");
+}
+
+#[test]
+fn test_validation() {
+    let lines: Vec<String> = ["0101", "011", "0121", "1100", "0101", "", "1100"].iter().map(|l| l.to_string()).collect();
+    let diagnostics = validate::validate(&lines, &Alphabet::binary());
+
+    assert_eq!(diagnostics, vec![
+        Diagnostic { line: 2, problem: Problem::Width { expected: 4, found: 3 } },
+        Diagnostic { line: 3, problem: Problem::Symbol { column: 2, symbol: '2' } },
+        Diagnostic { line: 6, problem: Problem::Width { expected: 4, found: 0 } },
+        Diagnostic { line: 5, problem: Problem::Duplicates { count: 2 } },
+    ]);
+    assert!(!diagnostics[3].is_fatal());
+
+    assert_eq!(validate::validate(&[], &Alphabet::binary()), vec![Diagnostic { line: 0, problem: Problem::Empty }]);
+}
+
+#[test]
+fn test_pipelines() {
+    let report = report(&SAMPLE);
+
+    let pipelines = Pipeline::parse_all("last = mask:0????, least@first; first = most:2, mask:1????",
+                                        report.alphabet()).unwrap();
+    assert_eq!(pipelines.len(), 2);
+
+    let last = pipelines[0].run(&report).unwrap();
+    assert_eq!((last.masks[0].removed, last.masks[0].left), (7, 5));
+    assert_eq!(last.survivor, 5);
+    assert_eq!(report.value(last.survivor).to_string(), "15");
+
+    // Two selected columns and a mask still leave several different lines
+    assert!(pipelines[1].run(&report).is_err());
+
+    let o2 = Pipeline::parse_all("O2 = most", report.alphabet()).unwrap();
+    assert_eq!(o2[0], Pipeline::o2(Tie::High));
+
+    assert!(Pipeline::parse_all("x = sideways", report.alphabet()).is_err());
+    assert!(Pipeline::parse_all("x = mask:1?2??", report.alphabet()).is_err());
+    assert!(Pipeline::parse_all("x = mask:1??", report.alphabet()).unwrap()[0].run(&report).is_err());
+
+    // Identical survivors are not ambiguous
+    let twins = self::report(&["101", "101", "000"]);
+    let twin  = Pipeline::parse_all("t = mask:1??", twins.alphabet()).unwrap();
+    assert_eq!(twin[0].run(&twins).unwrap().survivor, 0);
+}
+
+#[test]
+fn test_alphabet_lookup() {
+    let alphabet = Alphabet::new("ab\u{e9}").unwrap();
+    assert_eq!((alphabet.digit('a'), alphabet.digit('\u{e9}'), alphabet.digit('c')), (Some(0), Some(2), None));
+    assert_eq!(Alphabet::binary().digit('\u{1f600}'), None);
+
+    let too_many: String = (0..256).map(|i| char::from_u32(0x100 + i).unwrap()).collect();
+    assert!(Alphabet::new(&too_many).is_err());
+}
+
+#[test]
+fn test_dropped_lines_keep_file_line_numbers() {
+    let lines: Vec<String> = ["0x10"].iter().chain(SAMPLE.iter()).map(|l| l.to_string()).collect();
+    let diagnostics = validate::validate(&lines, &Alphabet::binary());
+    assert_eq!(diagnostics, vec![
+        Diagnostic { line: 1, problem: Problem::Width { expected: 5, found: 4 } },
+        Diagnostic { line: 1, problem: Problem::Symbol { column: 1, symbol: 'x' } },
+    ]);
+
+    let (lines, line_numbers) = usable_lines(lines, &diagnostics);
+    assert_eq!(lines.len(), 12);
+    assert_eq!(line_numbers[0], 2);
+
+    // 10111 is the fourth row of the report, but line 5 of the file
+    let report = Report::new(&lines, Alphabet::binary()).unwrap();
+    let (o2, co2) = life_support_steps(&report, &TiePolicy::default());
+    assert_eq!((o2.survivor, line_numbers[o2.survivor]), (3, 5));
+    assert_eq!(line_numbers[co2.survivor], 13);
+}
@@ -0,0 +1,99 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::report::Alphabet;
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    Empty,
+    Width { expected: usize, found: usize },
+    Symbol { column: usize, symbol: char },
+    // Identical lines share a value, so they are only counted, not listed
+    Duplicates { count: usize },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    // 1-based; 0 when the problem concerns the whole report
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl Diagnostic {
+    // Lines with these problems cannot take part in the ratings at all
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self.problem, Problem::Duplicates { .. })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.problem {
+            Problem::Empty => write!(f, "the report has no lines"),
+            Problem::Width { expected, found } => {
+                write!(f, "line {}: {} symbols wide instead of {}", self.line, found, expected)
+            },
+            Problem::Symbol { column, symbol } => {
+                write!(f, "line {}, column {}: '{}' is not in the alphabet", self.line, column + 1, symbol)
+            },
+            Problem::Duplicates { count } => {
+                write!(f, "line {}: first of {} line(s) repeating an earlier line", self.line, count)
+            },
+        }
+    }
+}
+
+// The expected width is the most common one, so that a single bad first
+// line does not get every other line flagged
+fn expected_width(lines: &[String]) -> usize {
+    let mut widths: HashMap<usize, usize> = HashMap::new();
+    for line in lines.iter() {
+        *widths.entry(line.chars().count()).or_insert(0) += 1;
+    }
+
+    let most = widths.values().copied().max().unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.chars().count())
+        .find(|w| widths[w] == most)
+        .unwrap_or(0)
+}
+
+pub fn validate(lines: &[String], alphabet: &Alphabet) -> Vec<Diagnostic> {
+    if lines.is_empty() {
+        return vec![Diagnostic { line: 0, problem: Problem::Empty }];
+    }
+
+    let width = expected_width(lines);
+    let mut diagnostics = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut duplicates: Option<Diagnostic> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let lineno = i + 1;
+        let found  = line.chars().count();
+        let mut valid = true;
+
+        if found != width {
+            diagnostics.push(Diagnostic { line: lineno, problem: Problem::Width { expected: width, found } });
+            valid = false;
+        }
+
+        for (column, symbol) in line.chars().enumerate() {
+            if alphabet.digit(symbol).is_none() {
+                diagnostics.push(Diagnostic { line: lineno, problem: Problem::Symbol { column, symbol } });
+                valid = false;
+            }
+        }
+
+        if valid && !seen.insert(line) {
+            match duplicates {
+                Some(Diagnostic { problem: Problem::Duplicates { ref mut count }, .. }) => *count += 1,
+                _ => duplicates = Some(Diagnostic { line: lineno, problem: Problem::Duplicates { count: 1 } }),
+            }
+        }
+    }
+
+    diagnostics.extend(duplicates);
+    diagnostics
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use crate::Board;
+use crate::pattern::Pattern;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Win {
+    pub board: usize,
+    // Position of the winning number in the draw order
+    pub draw: usize,
+    pub number: i32,
+    pub score: i32,
+    // Index of the pattern the board completed
+    pub pattern: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Ranking {
+    pub wins: Vec<Win>,
+    pub never: Vec<usize>,
+}
+
+// Which of the boards completing on the same draw to report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreak {
+    Input,
+    High,
+    Low,
+    All,
+}
+
+struct Tally {
+    marked: Vec<bool>,
+    // Marked cells of each pattern
+    counts: Vec<usize>,
+    unmarked: i32,
+    won: bool,
+}
+
+impl Ranking {
+    // Wins grouped by the draw they happened on, in draw order
+    pub fn rounds(&self) -> impl Iterator<Item = &[Win]> {
+        self.wins.chunk_by(|a, b| a.draw == b.draw)
+    }
+
+    pub fn first(&self, tie: TieBreak) -> Vec<&Win> {
+        self.rounds().next().map_or(Vec::new(), |round| pick(round, tie))
+    }
+
+    pub fn last(&self, tie: TieBreak) -> Vec<&Win> {
+        self.rounds().last().map_or(Vec::new(), |round| pick(round, tie))
+    }
+}
+
+// Equal scores fall back to input order
+fn pick(round: &[Win], tie: TieBreak) -> Vec<&Win> {
+    let chosen = match tie {
+        TieBreak::Input => round.first(),
+        TieBreak::High => round.iter().min_by_key(|win| std::cmp::Reverse(win.score)),
+        TieBreak::Low => round.iter().min_by_key(|win| win.score),
+        TieBreak::All => return round.iter().collect(),
+    };
+
+    chosen.into_iter().collect()
+}
+
+// The reverse index of a set of boards, built once and replayed for any
+// number of draw orders
+pub struct Game<'a> {
+    boards: &'a [Board],
+    patterns: &'a [Pattern],
+    // The patterns each cell takes part in
+    cell_patterns: Vec<Vec<usize>>,
+    index: HashMap<i32, Vec<(usize, usize, usize)>>,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(boards: &'a [Board], patterns: &'a [Pattern]) -> Self {
+        let mut cell_patterns = vec![Vec::new(); boards.first().map_or(0, |board| board.numbers.len())];
+        for (p, pattern) in patterns.iter().enumerate() {
+            for &cell in pattern.cells.iter() {
+                cell_patterns[cell].push(p);
+            }
+        }
+
+        let mut index: HashMap<i32, Vec<(usize, usize, usize)>> = HashMap::new();
+        for (b, board) in boards.iter().enumerate() {
+            for (cell, &number) in board.numbers.iter().enumerate() {
+                index.entry(number).or_default().push((b, cell / board.cols, cell % board.cols));
+            }
+        }
+
+        Self { boards, patterns, cell_patterns, index }
+    }
+
+    // Plays the whole game once and ranks the boards in the order they win,
+    // boards completing on the same draw are kept in input order.
+    pub fn play(&self, numbers: &[i32]) -> Ranking {
+        let mut tallies: Vec<Tally> = self.boards
+            .iter()
+            .map(|board| Tally {
+                marked: vec![false; board.numbers.len()],
+                counts: vec![0; self.patterns.len()],
+                unmarked: board.numbers.iter().sum(),
+                won: false,
+            })
+            .collect();
+
+        let mut wins = Vec::new();
+
+        for (draw, &number) in numbers.iter().enumerate() {
+            let positions = match self.index.get(&number) {
+                Some(positions) => positions,
+                None => continue,
+            };
+
+            // A number can sit on a board more than once, so only score a
+            // board once all of its cells for this draw have been marked
+            let mut completed: Vec<(usize, usize)> = Vec::new();
+
+            for &(b, row, col) in positions.iter() {
+                let tally = &mut tallies[b];
+                let cell  = row * self.boards[b].cols + col;
+
+                if tally.won || tally.marked[cell] {
+                    continue;
+                }
+
+                tally.marked[cell] = true;
+                tally.unmarked -= number;
+
+                for &p in self.cell_patterns[cell].iter() {
+                    tally.counts[p] += 1;
+                    if tally.counts[p] == self.patterns[p].cells.len() && completed.last().map(|c| c.0) != Some(b) {
+                        completed.push((b, p));
+                    }
+                }
+            }
+
+            for &(b, pattern) in completed.iter() {
+                tallies[b].won = true;
+                wins.push(Win { board: b, draw, number, score: number * tallies[b].unmarked, pattern });
+            }
+        }
+
+        let never = (0..self.boards.len()).filter(|&b| !tallies[b].won).collect();
+
+        Ranking { wins, never }
+    }
+}
+
+pub fn play(numbers: &[i32], boards: &[Board], patterns: &[Pattern]) -> Ranking {
+    Game::new(boards, patterns).play(numbers)
+}
@@ -1,184 +1,378 @@
+#[cfg(test)]
+mod tests;
+mod game;
+mod odds;
+mod pattern;
+mod validate;
+
 use std::env;
-use std::io::{self, BufReader, prelude::*};
-use std::fs::File;
+use std::fmt;
+use std::fs;
+use std::process;
+
+use crate::game::{TieBreak, Win};
+use crate::pattern::{Pattern, PatternSet};
 
 #[derive(Clone)]
 struct Board {
-    numbers: [[i32; 5]; 5],
-    marked:  [[bool; 5]; 5],
+    rows: usize,
+    cols: usize,
+    numbers: Vec<i32>,
 }
 
 impl Board {
-    fn new(lines: &[String; 5]) -> Self {
-        let mut numbers = [[0; 5]; 5];
+    fn new(rows: &[Vec<i32>]) -> Self {
+        let numbers = rows.iter().flatten().copied().collect();
+
+        Self { rows: rows.len(), cols: rows[0].len(), numbers }
+    }
+}
 
-        for (i, line) in lines.iter().enumerate() {
-            let row: Vec<i32> = line.replace("\n", "")
-                                    .split_whitespace()
-                                    .map(|n| n.parse().unwrap())
-                                    .collect();
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.numbers.iter().map(|n| n.to_string().len()).max().unwrap_or(1);
 
-            for j in 0..5 {
-                numbers[i][j] = row[j];
+        for row in self.numbers.chunks(self.cols) {
+            for (j, number) in row.iter().enumerate() {
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:width$}", number, width = width)?;
             }
+            writeln!(f)?;
         }
+        Ok(())
+    }
+}
 
-        let marked = [[false; 5]; 5];
+#[derive(Debug)]
+struct FormatError {
+    line: usize,
+    message: String,
+}
 
-        Self { numbers, marked }
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
     }
+}
 
-    fn mark_number(&mut self, n: i32) {
-        for i in 0..5 {
-            for j in 0..5 {
-                if self.numbers[i][j] == n {
-                    self.marked[i][j] = true;
-                }
-            }
-        }
-    }
+fn format_error(line: usize, message: impl fmt::Display) -> FormatError {
+    FormatError { line, message: message.to_string() }
+}
 
-    fn win(&self) -> bool {
-        // Check rows
-        if self.marked.iter().any(|row| row.iter().all(|&m| m)) {
-            return true;
+// Boards are separated by blank lines and take their size from the first
+// one, unless a size is given, in which case blank lines are optional.
+fn parse_game(text: &str, size: Option<(usize, usize)>) -> Result<(Vec<i32>, Vec<Board>), FormatError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+    let numbers = match lines.next() {
+        Some((_, line)) => line
+            .split(',')
+            .map(|n| n.trim().parse().map_err(|_| format_error(1, format_args!("'{}' is not a number", n))))
+            .collect::<Result<Vec<i32>, _>>()?,
+        None => return Err(format_error(1, "no numbers to draw")),
+    };
+
+    let mut blocks: Vec<Vec<(usize, Vec<i32>)>> = vec![Vec::new()];
+
+    for (line, text) in lines {
+        if text.is_empty() {
+            blocks.push(Vec::new());
+            continue;
         }
 
-        // Check columns
-        for j in 0..5 {
-            let mut all = true;
+        let row = text
+            .split_whitespace()
+            .map(|n| n.parse().map_err(|_| format_error(line, format_args!("'{}' is not a number", n))))
+            .collect::<Result<Vec<i32>, _>>()?;
 
-            for i in 0..5 {
-                if !self.marked[i][j] {
-                    all = false;
-                    break;
-                }
-            }
+        blocks.last_mut().unwrap().push((line, row));
+    }
 
-            if all {
-                return true;
-            }
-        }
+    blocks.retain(|block| !block.is_empty());
+    let rows: Vec<(usize, Vec<i32>)> = blocks.iter().flatten().cloned().collect();
+
+    let (height, width) = match size {
+        Some(size) => size,
+        None => match blocks.first() {
+            Some(block) => (block.len(), block[0].1.len()),
+            None => return Err(format_error(1, "no boards")),
+        },
+    };
 
-        return false;
+    if rows.is_empty() {
+        return Err(format_error(1, "no boards"));
     }
 
-    fn score(&self) -> i32 {
-        let mut sum = 0;
+    if let Some((line, row)) = rows.iter().find(|(_, row)| row.len() != width) {
+        return Err(format_error(*line, format_args!("row has {} numbers, expected {}", row.len(), width)));
+    }
 
-        for i in 0..5 {
-            for j in 0..5 {
-                if !self.marked[i][j] {
-                    sum += self.numbers[i][j];
-                }
-            }
+    // Without a given size, every block has to be a board on its own
+    if size.is_none() {
+        if let Some(block) = blocks.iter().find(|block| block.len() != height) {
+            return Err(format_error(block[0].0, format_args!("board has {} rows, expected {}", block.len(), height)));
         }
+    }
 
-        sum
+    if !rows.len().is_multiple_of(height) {
+        let start = rows[rows.len() - rows.len() % height].0;
+        return Err(format_error(start, format_args!("board has {} rows, expected {}", rows.len() % height, height)));
     }
+
+    let boards = rows
+        .chunks(height)
+        .map(|chunk| {
+            let rows: Vec<Vec<i32>> = chunk.iter().map(|(_, row)| row.clone()).collect();
+            Board::new(&rows)
+        })
+        .collect();
+
+    Ok((numbers, boards))
 }
 
-impl std::fmt::Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for i in 0..5 {
-            for j in 0..5 {
-                if j > 0 {
-                    write!(f, " ")?;
-                }
-                write!(f, "{:2}", self.numbers[i][j])?;
-            }
-            writeln!(f, "")?;
-        }
-        Ok(())
+struct Options {
+    fname: String,
+    size: Option<(usize, usize)>,
+    patterns: PatternSet,
+    pattern_file: Option<String>,
+    ranking: bool,
+    ties: TieBreak,
+    odds: bool,
+    pool: Option<(i32, i32)>,
+    trials: u64,
+    seed: u64,
+    check: bool,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: day4 <input> [--size ROWSxCOLS] [--patterns NAME,...] [--pattern-file FILE]");
+    eprintln!("                   [--diagonals] [--ranking] [--ties input|high|low|all]");
+    eprintln!("       day4 <input> --check [--size ROWSxCOLS] [--patterns NAME,...] [--pattern-file FILE]");
+    eprintln!("       day4 <input> --odds [--pool LOW-HIGH] [--trials N] [--seed N]");
+    eprintln!("                   [--ties input|high|low|all]");
+    eprintln!("       Without --size, boards are separated by blank lines and sized like the first one");
+    eprintln!("       --diagonals adds the diagonals to the patterns, which are rows and columns by default");
+    eprintln!("Patterns:");
+    for (name, description) in pattern::BUILTINS.iter() {
+        eprintln!("  {:<10} {}", name, description);
     }
+    eprintln!("  or any pattern defined in the pattern file");
+    process::exit(1);
 }
 
-fn bingo_setup(fname: &str) -> io::Result<(Vec<i32>, Vec<Board>)> {
-    let f = File::open(fname)?;
-    let mut reader = BufReader::new(f);
+fn fail(e: impl fmt::Display) -> ! {
+    eprintln!("Error: {}", e);
+    process::exit(1);
+}
+
+fn next_number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+    match args.next().and_then(|n| n.parse().ok()) {
+        Some(n) => n,
+        None => usage(),
+    }
+}
 
-    let mut buf = String::new();
-    reader.read_line(&mut buf)?;
+fn parse_size(spec: &str) -> Option<(usize, usize)> {
+    let (rows, cols) = spec.split_once('x').unwrap_or((spec, spec));
+    let size = (rows.parse().ok()?, cols.parse().ok()?);
 
-    let numbers: Vec<i32> = buf.replace("\n", "")
-                               .split(",")
-                               .map(|n| n.parse().unwrap())
-                               .collect();
+    Some(size).filter(|&(rows, cols)| rows > 0 && cols > 0)
+}
 
-    let mut board_str = [String::new(), String::new(), String::new(), String::new(), String::new()];
-    let mut boards: Vec<Board> = Vec::new();
+fn parse_pool(spec: &str) -> Option<(i32, i32)> {
+    let (low, high) = spec.split_once('-')?;
+    let pool = (low.parse().ok()?, high.parse().ok()?);
 
-    let mut lines = reader.lines();
+    Some(pool).filter(|&(low, high)| low <= high)
+}
 
-    while let Some(_) = lines.next() {
-        for i in 0..5 {
-            board_str[i] = lines.next().unwrap()?;
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+
+    let mut fname = None;
+    let mut size  = None;
+    let mut patterns  = PatternSet::default();
+    let mut pattern_file = None;
+    let mut diagonals = false;
+    let mut ranking   = false;
+    let mut ties = TieBreak::Input;
+    let mut odds = false;
+    let mut pool = None;
+    let mut trials = 10000;
+    let mut seed   = 1;
+    let mut check  = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => size = Some(args.next().as_deref().and_then(parse_size).unwrap_or_else(|| usage())),
+            "--patterns" => {
+                let names = args.next().unwrap_or_else(|| usage());
+                patterns.names = names.split(',').map(|name| name.trim().to_string()).collect();
+            },
+            "--pattern-file" => pattern_file = Some(args.next().unwrap_or_else(|| usage())),
+            "--diagonals" => diagonals = true,
+            "--ranking" => ranking = true,
+            "--ties" => {
+                ties = match args.next().as_deref() {
+                    Some("input") => TieBreak::Input,
+                    Some("high") => TieBreak::High,
+                    Some("low") => TieBreak::Low,
+                    Some("all") => TieBreak::All,
+                    _ => usage(),
+                };
+            },
+            "--odds" => odds = true,
+            "--check" => check = true,
+            "--pool" => pool = Some(args.next().as_deref().and_then(parse_pool).unwrap_or_else(|| usage())),
+            "--trials" => trials = next_number(&mut args),
+            "--seed" => seed = next_number(&mut args),
+            _ if fname.is_none() => fname = Some(arg),
+            _ => usage(),
         }
-        boards.push(Board::new(&board_str));
     }
 
-    Ok((numbers, boards))
-}
+    if diagonals {
+        patterns.names.push("diagonals".to_string());
+    }
 
-fn bingo_win(numbers: &Vec<i32>, boards: &Vec<Board>) -> Option<(Board, i32)> {
-    let mut local_boards = (*boards).clone();
+    match fname {
+        Some(fname) => Options {
+            fname, size, patterns, pattern_file, ranking, ties, odds, pool, trials, seed, check,
+        },
+        None => usage(),
+    }
+}
 
-    for &number in numbers.iter() {
-        for board in local_boards.iter_mut() {
-            board.mark_number(number);
+fn print_odds(numbers: &[i32], boards: &[Board], patterns: &[Pattern], options: &Options) {
+    // Without an explicit pool, any of the listed draws can come up
+    let pool: Vec<i32> = match options.pool {
+        Some((low, high)) => (low..=high).collect(),
+        None => {
+            let mut pool = numbers.to_vec();
+            pool.sort_unstable();
+            pool.dedup();
+            pool
+        },
+    };
+
+    let odds = odds::estimate(boards, patterns, &pool, options.ties, options.trials, options.seed);
+
+    if pool.len() <= odds::EXACT_POOL {
+        println!("Exact odds over all orders of {} numbers", pool.len());
+    } else {
+        println!("Estimated odds over {} random orders of {} numbers (seed {})", options.trials, pool.len(), options.seed);
+    }
 
-            if board.win() {
-                return Some((board.clone(), number * board.score()));
-            }
+    let show = |estimate: &odds::Estimate| {
+        let (low, high) = estimate.interval();
+        if estimate.exact {
+            format!("{:>7.3}%", 100.0 * estimate.probability())
+        } else {
+            format!("{:>7.3}% [{:>7.3}%, {:>7.3}%]", 100.0 * estimate.probability(), 100.0 * low, 100.0 * high)
         }
-    }
+    };
+
+    let rows: Vec<(usize, String, String)> = odds
+        .iter()
+        .map(|entry| (entry.board + 1, show(&entry.first), show(&entry.last)))
+        .collect();
+    let width = rows.iter().map(|(_, first, _)| first.len()).max().unwrap_or(0).max("Wins first".len());
 
-    None
+    println!("{:>6}  {:<width$}  Finishes last", "Board", "Wins first");
+    for (board, first, last) in rows.iter() {
+        println!("{:>6}  {:<width$}  {}", board, first, last);
+    }
 }
 
-fn bingo_lose(numbers: &Vec<i32>, boards: &Vec<Board>) -> Option<(Board, i32)> {
-    let mut local_boards = (*boards).clone();
-    let mut nums = numbers.iter();
+fn main() {
+    let mut options = parse_args();
 
-    let mut number: i32 = 0;
+    if let Some(fname) = &options.pattern_file {
+        let text = fs::read_to_string(fname).unwrap_or_else(|e| fail(format_args!("{}: {}", fname, e)));
+        options.patterns.custom = pattern::parse_file(&text).unwrap_or_else(|e| fail(format_args!("{}: {}", fname, e)));
+    }
+
+    let fname = &options.fname;
+    let text  = fs::read_to_string(fname).unwrap_or_else(|e| fail(format_args!("{}: {}", fname, e)));
+
+    let diagnostics = validate::validate(&text, options.size, &options.patterns);
+
+    if options.check {
+        for diagnostic in diagnostics.iter() {
+            println!("{}: {}", fname, diagnostic);
+        }
 
-    // Get it down to 1 board
-    while local_boards.len() > 1 {
-        number = *nums.next().unwrap();
-        for board in local_boards.iter_mut() {
-            board.mark_number(number);
+        if diagnostics.is_empty() {
+            println!("{}: no problems found", fname);
+            return;
         }
-        local_boards.retain(|board| !board.win());
+        process::exit(1);
     }
 
-    if local_boards.is_empty() {
-        return None;
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{}: {}", fname, diagnostic);
     }
 
-    let last = &mut local_boards[0];
+    let fatal = diagnostics.iter().filter(|d| d.is_fatal()).count();
+    if fatal > 0 {
+        fail(format_args!("{} problem(s) in {}, cannot read the boards", fatal, fname));
+    }
+
+    let (numbers, boards) = parse_game(&text, options.size).unwrap_or_else(|e| fail(format_args!("{}: {}", fname, e)));
 
-    // Keep drawing numbers until the board is complete
-    while !last.win() {
-        number = *nums.next().unwrap();
-        last.mark_number(number);
+    let patterns = options.patterns.resolve(boards[0].rows, boards[0].cols).unwrap_or_else(|e| fail(e));
+
+    if options.odds {
+        print_odds(&numbers, &boards, &patterns, &options);
+        return;
     }
 
-    Some((last.clone(), number * last.score()))
-}
+    let ranking = game::play(&numbers, &boards, &patterns);
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+    let print_wins = |title, wins: Vec<&Win>| {
+        if let Some(win) = wins.first() {
+            let round: Vec<String> = ranking
+                .wins
+                .iter()
+                .filter(|other| other.draw == win.draw)
+                .map(|other| format!("board {} ({})", other.board + 1, other.score))
+                .collect();
 
-    let (numbers, boards) = bingo_setup(&args[1]).unwrap();
+            if round.len() > 1 {
+                println!("{} boards complete on draw {} (number {}): {}",
+                         round.len(), win.draw + 1, win.number, round.join(", "));
+            }
+        }
 
-    let (winner, hiscore) = bingo_win(&numbers, &boards).unwrap();
-    println!("Winning board:");
-    print!("{}", winner);
-    println!("Score: {}", hiscore);
-    println!();
+        for win in wins {
+            println!("{} board ({}, {}):", title, win.board + 1, patterns[win.pattern].name);
+            print!("{}", boards[win.board]);
+            println!("Score: {}", win.score);
+        }
+    };
+
+    if ranking.wins.is_empty() {
+        println!("No board wins");
+    } else {
+        print_wins("Winning", ranking.first(options.ties));
+        println!();
+        print_wins("Losing", ranking.last(options.ties));
+    }
 
-    let (loser, loscore) = bingo_lose(&numbers, &boards).unwrap();
-    println!("Losing board:");
-    print!("{}", loser);
-    println!("Score: {}", loscore);
+    if options.ranking {
+        println!();
+        println!("{:>4} {:>6} {:>6} {:>6} {:>8}  Pattern", "Rank", "Board", "Draw", "Number", "Score");
+        for (rank, win) in ranking.wins.iter().enumerate() {
+            println!("{:>4} {:>6} {:>6} {:>6} {:>8}  {}", rank + 1, win.board + 1, win.draw + 1, win.number, win.score,
+                     patterns[win.pattern].name);
+        }
+    }
+
+    if !ranking.never.is_empty() {
+        let never: Vec<String> = ranking.never.iter().map(|b| (b + 1).to_string()).collect();
+        println!();
+        println!("Never winning: board(s) {}", never.join(", "));
+    }
 }
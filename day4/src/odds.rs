@@ -0,0 +1,134 @@
+use crate::Board;
+use crate::game::{Game, Ranking, TieBreak, Win};
+use crate::pattern::Pattern;
+
+// Pools up to this size are played in every possible order
+pub const EXACT_POOL: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    // Fractional when boards share a win, see `tally`
+    pub hits: f64,
+    pub trials: u64,
+    pub exact: bool,
+}
+
+impl Estimate {
+    pub fn probability(&self) -> f64 {
+        if self.trials == 0 { 0.0 } else { self.hits / self.trials as f64 }
+    }
+
+    // 95% Wilson score interval, which behaves near 0 and 1 unlike the
+    // plain normal approximation
+    pub fn interval(&self) -> (f64, f64) {
+        let p = self.probability();
+        if self.exact || self.trials == 0 {
+            return (p, p);
+        }
+
+        let z = 1.96;
+        let n = self.trials as f64;
+        let denominator = 1.0 + z * z / n;
+        let centre = (p + z * z / (2.0 * n)) / denominator;
+        let spread = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+
+        ((centre - spread).max(0.0), (centre + spread).min(1.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Odds {
+    pub board: usize,
+    pub first: Estimate,
+    pub last: Estimate,
+}
+
+// Boards sharing the first or the last draw are split by `ties` the same way
+// as for a single game; when it keeps all of them, each gets an equal share
+// of the order so that the odds of a draw still add up to 100%
+fn tally(odds: &mut [Odds], ranking: &Ranking, ties: TieBreak) {
+    let share = |wins: &[&Win]| 1.0 / wins.len() as f64;
+
+    let first = ranking.first(ties);
+    for win in first.iter() {
+        odds[win.board].first.hits += share(&first);
+    }
+
+    let last = ranking.last(ties);
+    for win in last.iter() {
+        odds[win.board].last.hits += share(&last);
+    }
+}
+
+// Heap's algorithm, calling `f` once for every ordering of `pool`
+fn permutations(pool: &mut [i32], mut f: impl FnMut(&[i32])) {
+    let mut counters = vec![0; pool.len()];
+    f(pool);
+
+    let mut i = 1;
+    while i < pool.len() {
+        if counters[i] < i {
+            let j = if i % 2 == 0 { 0 } else { counters[i] };
+            pool.swap(j, i);
+            f(pool);
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+// Odds of each board to win first and to finish last when `pool` is drawn in
+// random order, best first. Small pools are solved exactly, larger ones are
+// sampled `trials` times with a seeded xorshift64* generator.
+pub fn estimate(boards: &[Board], patterns: &[Pattern], pool: &[i32], ties: TieBreak, trials: u64, seed: u64)
+    -> Vec<Odds>
+{
+    let game  = Game::new(boards, patterns);
+    let exact = pool.len() <= EXACT_POOL;
+
+    let mut odds: Vec<Odds> = (0..boards.len())
+        .map(|board| {
+            let estimate = Estimate { hits: 0.0, trials: 0, exact };
+            Odds { board, first: estimate.clone(), last: estimate }
+        })
+        .collect();
+
+    let mut pool = pool.to_vec();
+    let mut played = 0;
+
+    if exact {
+        permutations(&mut pool, |order| {
+            tally(&mut odds, &game.play(order), ties);
+            played += 1;
+        });
+    } else {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        let mut next = move || {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        };
+
+        for _ in 0..trials {
+            // Fisher-Yates
+            for i in (1..pool.len()).rev() {
+                pool.swap(i, (next() % (i as u64 + 1)) as usize);
+            }
+
+            tally(&mut odds, &game.play(&pool), ties);
+            played += 1;
+        }
+    }
+
+    for entry in odds.iter_mut() {
+        entry.first.trials = played;
+        entry.last.trials  = played;
+    }
+
+    odds.sort_by(|a, b| b.first.hits.total_cmp(&a.first.hits).then(a.board.cmp(&b.board)));
+    odds
+}
@@ -0,0 +1,168 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    // Indices of the cells to mark, row by row
+    pub cells: Vec<usize>,
+}
+
+pub const BUILTINS: [(&str, &str); 7] = [
+    ("rows", "any full row"),
+    ("columns", "any full column"),
+    ("diagonals", "either full diagonal, square boards only"),
+    ("corners", "the four corners"),
+    ("x", "both diagonals at once, square boards only"),
+    ("blackout", "every cell"),
+    ("stamps", "a 2x2 block in any corner"),
+];
+
+// A pattern from a pattern file, drawn with X for the cells to mark
+#[derive(Debug, Clone, PartialEq)]
+pub struct Custom {
+    pub name: String,
+    pub line: usize,
+    pub grid: Vec<Vec<bool>>,
+}
+
+// Parses named grids like
+//
+//     pattern plus
+//     ..X..
+//     XXXXX
+//     ..X..
+//
+// where blank lines and lines starting with '#' are ignored
+pub fn parse_file(text: &str) -> Result<Vec<Custom>, String> {
+    let mut patterns: Vec<Custom> = Vec::new();
+
+    for (i, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("pattern ") {
+            patterns.push(Custom { name: name.trim().to_string(), line: i, grid: Vec::new() });
+            continue;
+        }
+
+        let pattern = match patterns.last_mut() {
+            Some(pattern) => pattern,
+            None => return Err(format!("line {}: expected 'pattern NAME' first", i)),
+        };
+
+        let row = line
+            .chars()
+            .map(|c| match c {
+                'X' | 'x' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(format!("line {}: '{}' is neither X nor '.'", i, c)),
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+
+        if pattern.grid.first().is_some_and(|first| first.len() != row.len()) {
+            return Err(format!("line {}: row is {} cells wide instead of {}", i, row.len(), pattern.grid[0].len()));
+        }
+
+        pattern.grid.push(row);
+    }
+
+    match patterns.iter().find(|pattern| !pattern.grid.iter().flatten().any(|&cell| cell)) {
+        Some(pattern) => Err(format!("line {}: pattern '{}' has no cells to mark", pattern.line, pattern.name)),
+        None => Ok(patterns),
+    }
+}
+
+fn builtin(name: &str, rows: usize, cols: usize) -> Option<Result<Vec<Pattern>, String>> {
+    let cell = |i: usize, j: usize| i * cols + j;
+    let single = |name: &str, cells: Vec<usize>| vec![Pattern { name: name.to_string(), cells }];
+    let square = |patterns: Vec<Pattern>| if rows == cols {
+        Ok(patterns)
+    } else {
+        Err(format!("pattern '{}' needs square boards, these are {}x{}", name, rows, cols))
+    };
+
+    let diagonal: Vec<usize> = (0..rows.min(cols)).map(|i| cell(i, i)).collect();
+    let anti: Vec<usize> = (0..rows.min(cols)).map(|i| cell(i, cols - 1 - i)).collect();
+
+    let patterns = match name {
+        "rows" => Ok((0..rows)
+            .map(|i| Pattern { name: format!("row {}", i + 1), cells: (0..cols).map(|j| cell(i, j)).collect() })
+            .collect()),
+        "columns" => Ok((0..cols)
+            .map(|j| Pattern { name: format!("column {}", j + 1), cells: (0..rows).map(|i| cell(i, j)).collect() })
+            .collect()),
+        "diagonals" => square(vec![
+            Pattern { name: "diagonal".to_string(), cells: diagonal },
+            Pattern { name: "anti-diagonal".to_string(), cells: anti },
+        ]),
+        "corners" => {
+            let mut cells = vec![cell(0, 0), cell(0, cols - 1), cell(rows - 1, 0), cell(rows - 1, cols - 1)];
+            cells.sort_unstable();
+            cells.dedup();
+            Ok(single("corners", cells))
+        },
+        "x" => {
+            let mut cells: Vec<usize> = diagonal.into_iter().chain(anti).collect();
+            cells.sort_unstable();
+            cells.dedup();
+            square(single("x", cells))
+        },
+        "blackout" => Ok(single("blackout", (0..rows * cols).collect())),
+        "stamps" if rows < 2 || cols < 2 => Err(format!("pattern 'stamps' needs boards of at least 2x2, these are {}x{}", rows, cols)),
+        "stamps" => {
+            let corners = [("top-left", 0, 0), ("top-right", 0, cols - 2),
+                           ("bottom-left", rows - 2, 0), ("bottom-right", rows - 2, cols - 2)];
+            Ok(corners
+                .iter()
+                .map(|&(corner, i, j)| Pattern {
+                    name: format!("stamp {}", corner),
+                    cells: vec![cell(i, j), cell(i, j + 1), cell(i + 1, j), cell(i + 1, j + 1)],
+                })
+                .collect())
+        },
+        _ => return None,
+    };
+
+    Some(patterns)
+}
+
+// The names of the patterns a game is played with, resolved once the size of
+// the boards is known
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternSet {
+    pub names: Vec<String>,
+    pub custom: Vec<Custom>,
+}
+
+impl Default for PatternSet {
+    fn default() -> Self {
+        Self { names: vec!["rows".to_string(), "columns".to_string()], custom: Vec::new() }
+    }
+}
+
+impl PatternSet {
+    pub fn resolve(&self, rows: usize, cols: usize) -> Result<Vec<Pattern>, String> {
+        let mut patterns = Vec::new();
+
+        for name in self.names.iter() {
+            if let Some(builtin) = builtin(name, rows, cols) {
+                patterns.extend(builtin?);
+                continue;
+            }
+
+            let custom = match self.custom.iter().find(|custom| custom.name == *name) {
+                Some(custom) => custom,
+                None => return Err(format!("unknown pattern '{}'", name)),
+            };
+
+            if custom.grid.len() != rows || custom.grid[0].len() != cols {
+                return Err(format!("pattern '{}' is {}x{}, the boards are {}x{}",
+                                   name, custom.grid.len(), custom.grid[0].len(), rows, cols));
+            }
+
+            let cells = custom.grid.iter().flatten().enumerate().filter(|(_, &x)| x).map(|(i, _)| i).collect();
+            patterns.push(Pattern { name: name.clone(), cells });
+        }
+
+        Ok(patterns)
+    }
+}
@@ -0,0 +1,181 @@
+use crate::*;
+use crate::game::{Ranking, TieBreak, Win};
+use crate::pattern::{Pattern, PatternSet};
+use crate::validate::{Diagnostic, Problem};
+
+const SAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+fn patterns(boards: &[Board], names: &str) -> Vec<Pattern> {
+    let set = PatternSet { names: names.split(',').map(str::to_string).collect(), custom: Vec::new() };
+    set.resolve(boards[0].rows, boards[0].cols).unwrap()
+}
+
+#[test]
+fn test_sample() {
+    let (numbers, boards) = parse_game(SAMPLE, None).unwrap();
+    assert_eq!(boards.len(), 3);
+
+    let ranking = game::play(&numbers, &boards, &patterns(&boards, "rows,columns"));
+    assert_eq!(ranking.wins, vec![
+        Win { board: 2, draw: 11, number: 24, score: 4512, pattern: 0 },
+        Win { board: 0, draw: 13, number: 16, score: 2192, pattern: 2 },
+        Win { board: 1, draw: 14, number: 13, score: 1924, pattern: 7 },
+    ]);
+    assert!(ranking.never.is_empty());
+}
+
+#[test]
+fn test_board_sizes() {
+    let small = "5,1,9,2,3\n\n1 2 3\n4 5 6\n7 8 9\n\n\n";
+    let (numbers, boards) = parse_game(small, None).unwrap();
+    assert_eq!((boards[0].rows, boards[0].cols), (3, 3));
+    assert_eq!(game::play(&numbers, &boards, &patterns(&boards, "rows,columns")).wins[0].score, 75);
+    assert_eq!(game::play(&numbers, &boards, &patterns(&boards, "rows,columns,diagonals")).wins[0].score, 270);
+
+    // A given size does not need blank lines between the boards
+    let wide = "1,2,3,4,5,6\n1 2 3\n4 5 6\n6 5 4\n3 2 7\n";
+    let (numbers, boards) = parse_game(wide, Some((2, 3))).unwrap();
+    assert_eq!(boards.len(), 2);
+    let scores: Vec<i32> = game::play(&numbers, &boards, &patterns(&boards, "rows,columns")).wins.iter().map(|win| win.score).collect();
+    assert_eq!(scores, vec![45, 65]);
+
+    let line = |text, size| match parse_game(text, size) {
+        Err(FormatError { line, .. }) => line,
+        _ => 0,
+    };
+    assert_eq!(line("1,2\n\n1 2\n3 4\n\n1 2\n", None), 6);
+    assert_eq!(line("1,2\n\n1 2\n3\n", None), 4);
+    assert_eq!(line("1,x\n\n1 2\n3 4\n", None), 1);
+    assert_eq!(line("1,2\n\n1 2\n3 4\n5 6\n", Some((2, 2))), 5);
+}
+
+#[test]
+fn test_ranking() {
+    // The last board never completes, the second one holds 5 twice
+    let text = "5,1,2,3,5,4\n\n1 2\n3 9\n\n5 5\n8 8\n\n7 8\n9 6\n";
+    let (numbers, boards) = parse_game(text, None).unwrap();
+
+    assert_eq!(game::play(&numbers, &boards, &patterns(&boards, "rows,columns")), Ranking {
+        wins: vec![
+            Win { board: 1, draw: 0, number: 5, score: 80, pattern: 0 },
+            Win { board: 0, draw: 2, number: 2, score: 24, pattern: 0 },
+        ],
+        never: vec![2],
+    });
+}
+
+#[test]
+fn test_ties() {
+    let text = "1,2,7,5,6\n\n1 2\n3 4\n\n2 1\n5 6\n\n1 9\n2 8\n\n7 4\n5 6\n";
+    let (numbers, boards) = parse_game(text, None).unwrap();
+    let ranking = game::play(&numbers, &boards, &patterns(&boards, "rows,columns"));
+
+    let boards = |wins: Vec<&Win>| wins.iter().map(|win| win.board).collect::<Vec<_>>();
+    assert_eq!(ranking.rounds().count(), 2);
+    assert_eq!(boards(ranking.first(TieBreak::Input)), vec![0]);
+    assert_eq!(boards(ranking.first(TieBreak::High)), vec![2]);
+    assert_eq!(boards(ranking.first(TieBreak::Low)), vec![0]);
+    assert_eq!(boards(ranking.first(TieBreak::All)), vec![0, 1, 2]);
+    assert_eq!(boards(ranking.last(TieBreak::All)), vec![3]);
+}
+
+#[test]
+fn test_odds() {
+    let (_, boards) = parse_game("1\n\n1 2\n5 6\n\n3 4\n5 6\n", None).unwrap();
+
+    // Both boards need two of four numbers, so each wins half the orders
+    let exact = odds::estimate(&boards, &patterns(&boards, "rows,columns"), &[1, 2, 3, 4], TieBreak::All, 0, 0);
+    assert_eq!(exact[0].first, odds::Estimate { hits: 12.0, trials: 24, exact: true });
+    assert_eq!(exact[1].last.probability(), 0.5);
+
+    let pool: Vec<i32> = (1..=4).chain(7..=12).collect();
+    let sampled = odds::estimate(&boards, &patterns(&boards, "rows,columns"), &pool, TieBreak::All, 1000, 7);
+    assert_eq!(sampled, odds::estimate(&boards, &patterns(&boards, "rows,columns"), &pool, TieBreak::All, 1000, 7));
+    assert_eq!(sampled[0].first.trials, 1000);
+    assert_eq!(sampled[0].first.hits + sampled[1].first.hits, 1000.0);
+
+    // Boards that always win together share the order, or follow --ties
+    let (_, tied) = parse_game("1\n\n1 2\n5 6\n\n1 2\n7 8\n", None).unwrap();
+    let shared = odds::estimate(&tied, &patterns(&tied, "rows"), &[1, 2], TieBreak::All, 0, 0);
+    assert_eq!((shared[0].first.probability(), shared[1].first.probability()), (0.5, 0.5));
+    let high = odds::estimate(&tied, &patterns(&tied, "rows"), &[1, 2], TieBreak::High, 0, 0);
+    assert_eq!((high[0].board, high[0].first.probability(), high[1].first.probability()), (1, 1.0, 0.0));
+
+    let (low, high) = odds::Estimate { hits: 50.0, trials: 100, exact: false }.interval();
+    assert!((low - 0.4038).abs() < 1e-4 && (high - 0.5962).abs() < 1e-4);
+}
+
+#[test]
+fn test_validation() {
+    assert!(validate::validate(SAMPLE, None, &PatternSet::default()).is_empty());
+
+    let broken = validate::validate("1,2,x\n\n1 2\n3 4\n\n5 6\n7\n\n8 9\n\n\n", None, &PatternSet::default());
+    assert_eq!(broken, vec![
+        Diagnostic { line: 1, problem: Problem::NotNumber { text: "x".to_string() } },
+        Diagnostic { line: 7, problem: Problem::Width { expected: 2, found: 1 } },
+        Diagnostic { line: 9, problem: Problem::Height { expected: 2, found: 1 } },
+    ]);
+    assert!(broken.iter().all(Diagnostic::is_fatal));
+
+    let dubious = validate::validate("1,2,3,2\n\n1 2\n3 1\n\n4 3\n1 5\n", None, &PatternSet::default());
+    assert_eq!(dubious, vec![
+        Diagnostic { line: 1, problem: Problem::DuplicateDraw { number: 2, draw: 4, first: 2 } },
+        Diagnostic { line: 4, problem: Problem::DuplicateCell { number: 1, of: 3 } },
+        Diagnostic { line: 6, problem: Problem::NeverDrawn { number: 4 } },
+        Diagnostic { line: 6, problem: Problem::CannotWin { board: 2 } },
+        Diagnostic { line: 7, problem: Problem::NeverDrawn { number: 5 } },
+    ]);
+    assert!(!dubious.iter().any(Diagnostic::is_fatal));
+
+    assert_eq!(validate::validate("", None, &PatternSet::default()), vec![
+        Diagnostic { line: 0, problem: Problem::NoBoards },
+        Diagnostic { line: 1, problem: Problem::NoDraws },
+    ]);
+}
+
+#[test]
+fn test_patterns() {
+    let (_, boards) = parse_game("1\n\n1 2 3\n4 5 6\n7 8 9\n", None).unwrap();
+
+    let builtins = patterns(&boards, "corners,x,stamps,blackout");
+    assert_eq!(builtins[0].cells, vec![0, 2, 6, 8]);
+    assert_eq!(builtins[1].cells, vec![0, 2, 4, 6, 8]);
+    assert_eq!((builtins[5].name.as_str(), builtins[5].cells.clone()), ("stamp bottom-right", vec![4, 5, 7, 8]));
+    assert_eq!(builtins[6].cells.len(), 9);
+
+    let ranking = game::play(&[1, 3, 7, 5, 9], &boards, &builtins);
+    assert_eq!(ranking.wins, vec![Win { board: 0, draw: 4, number: 9, score: 180, pattern: 0 }]);
+
+    let custom = pattern::parse_file("# a plus sign\npattern plus\n.X.\nXXX\n.X.\n").unwrap();
+    let set = PatternSet { names: vec!["plus".to_string(), "corners".to_string()], custom };
+    let plus = set.resolve(3, 3).unwrap();
+    assert_eq!(plus[0], Pattern { name: "plus".to_string(), cells: vec![1, 3, 4, 5, 7] });
+    assert_eq!(game::play(&[2, 4, 5, 6, 8], &boards, &plus).wins[0].score, 160);
+
+    assert!(set.resolve(5, 5).is_err());
+    assert!(PatternSet { names: vec!["x".to_string()], custom: Vec::new() }.resolve(2, 3).is_err());
+    assert!(PatternSet { names: vec!["nope".to_string()], custom: Vec::new() }.resolve(3, 3).is_err());
+    assert!(pattern::parse_file("pattern bad\nX?X\n").is_err());
+    assert!(pattern::parse_file("pattern empty\n...\n").is_err());
+    assert!(pattern::parse_file("XX\n").is_err());
+}
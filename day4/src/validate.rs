@@ -0,0 +1,216 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{Board, game};
+use crate::pattern::PatternSet;
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    NoDraws,
+    NoBoards,
+    NotNumber { text: String },
+    Width { expected: usize, found: usize },
+    Height { expected: usize, found: usize },
+    DuplicateCell { number: i32, of: usize },
+    DuplicateDraw { number: i32, draw: usize, first: usize },
+    NeverDrawn { number: i32 },
+    CannotWin { board: usize },
+    Patterns { message: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    // 1-based; 0 when the problem concerns the whole input
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl Diagnostic {
+    // With these problems the boards cannot be read at all
+    pub fn is_fatal(&self) -> bool {
+        matches!(self.problem, Problem::NoDraws | Problem::NoBoards | Problem::NotNumber { .. } |
+                               Problem::Width { .. } | Problem::Height { .. } | Problem::Patterns { .. })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.problem {
+            Problem::NoDraws => write!(f, "line {}: no numbers to draw", self.line),
+            Problem::NoBoards => write!(f, "there are no boards"),
+            Problem::NotNumber { text } => write!(f, "line {}: '{}' is not a number", self.line, text),
+            Problem::Width { expected, found } => {
+                write!(f, "line {}: row has {} numbers instead of {}", self.line, found, expected)
+            },
+            Problem::Height { expected, found } => {
+                write!(f, "line {}: board has {} rows instead of {}", self.line, found, expected)
+            },
+            Problem::DuplicateCell { number, of } => {
+                write!(f, "line {}: {} is already on this board at line {}", self.line, number, of)
+            },
+            Problem::DuplicateDraw { number, draw, first } => {
+                write!(f, "line {}: {} is drawn again as draw {}, first drawn as draw {}", self.line, number, draw, first)
+            },
+            Problem::NeverDrawn { number } => write!(f, "line {}: {} is never drawn", self.line, number),
+            Problem::CannotWin { board } => {
+                write!(f, "line {}: board {} cannot win with these draws", self.line, board)
+            },
+            Problem::Patterns { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+// The most common value, ties going to the one seen first
+fn most_common(values: &[usize]) -> usize {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for &value in values.iter() {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+
+    let most = counts.values().copied().max().unwrap_or(0);
+    values.iter().copied().find(|v| counts[v] == most).unwrap_or(0)
+}
+
+// Reports every problem in a game instead of stopping at the first one like
+// `parse_game`. Without a given size, the expected one is the most common,
+// so that a single bad board does not get every other one flagged.
+pub fn validate(text: &str, size: Option<(usize, usize)>, patterns: &PatternSet) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+    let mut numbers = |line: usize, text: &str, separator: Option<char>| -> Vec<i32> {
+        let tokens: Vec<&str> = match separator {
+            Some(separator) => text.split(separator).map(str::trim).collect(),
+            None => text.split_whitespace().collect(),
+        };
+
+        tokens
+            .into_iter()
+            .filter_map(|token| match token.parse() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    diagnostics.push(Diagnostic { line, problem: Problem::NotNumber { text: token.to_string() } });
+                    None
+                },
+            })
+            .collect()
+    };
+
+    let draws = match lines.next() {
+        Some((_, line)) if !line.is_empty() => numbers(1, line, Some(',')),
+        _ => Vec::new(),
+    };
+
+    // Each row keeps its count of numbers, so that a bad cell is not also
+    // reported as a short row
+    let mut blocks: Vec<Vec<(usize, usize, Vec<i32>)>> = vec![Vec::new()];
+    for (line, text) in lines {
+        if text.is_empty() {
+            blocks.push(Vec::new());
+        } else {
+            let found = text.split_whitespace().count();
+            blocks.last_mut().unwrap().push((line, found, numbers(line, text, None)));
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
+
+    if draws.is_empty() && diagnostics.is_empty() {
+        diagnostics.push(Diagnostic { line: 1, problem: Problem::NoDraws });
+    }
+
+    if blocks.is_empty() {
+        diagnostics.push(Diagnostic { line: 0, problem: Problem::NoBoards });
+        diagnostics.sort_by_key(|d| d.line);
+        return diagnostics;
+    }
+
+    let rows: Vec<&(usize, usize, Vec<i32>)> = blocks.iter().flatten().collect();
+    let (height, width) = match size {
+        Some(size) => size,
+        None => {
+            let heights: Vec<usize> = blocks.iter().map(|block| block.len()).collect();
+            let widths: Vec<usize> = rows.iter().map(|(_, found, _)| *found).collect();
+            (most_common(&heights), most_common(&widths))
+        },
+    };
+
+    for &(line, found, _) in rows.iter() {
+        if *found != width {
+            diagnostics.push(Diagnostic { line: *line, problem: Problem::Width { expected: width, found: *found } });
+        }
+    }
+
+    match size {
+        Some(_) if !rows.len().is_multiple_of(height) => {
+            let found = rows.len() % height;
+            let line  = rows[rows.len() - found].0;
+            diagnostics.push(Diagnostic { line, problem: Problem::Height { expected: height, found } });
+        },
+        Some(_) => (),
+        None => {
+            for block in blocks.iter().filter(|block| block.len() != height) {
+                let problem = Problem::Height { expected: height, found: block.len() };
+                diagnostics.push(Diagnostic { line: block[0].0, problem });
+            }
+        },
+    }
+
+    let patterns = match patterns.resolve(height, width) {
+        Ok(patterns) => patterns,
+        Err(message) => {
+            diagnostics.push(Diagnostic { line: 0, problem: Problem::Patterns { message } });
+            Vec::new()
+        },
+    };
+
+    if diagnostics.iter().any(Diagnostic::is_fatal) {
+        diagnostics.sort_by_key(|d| d.line);
+        return diagnostics;
+    }
+
+    let mut first_draw: HashMap<i32, usize> = HashMap::new();
+    for (i, &number) in draws.iter().enumerate() {
+        match first_draw.get(&number) {
+            Some(&first) => {
+                diagnostics.push(Diagnostic { line: 1, problem: Problem::DuplicateDraw { number, draw: i + 1, first } });
+            },
+            None => {
+                first_draw.insert(number, i + 1);
+            },
+        }
+    }
+
+    let mut boards = Vec::new();
+    for chunk in rows.chunks(height) {
+        let mut seen: HashMap<i32, usize> = HashMap::new();
+        let mut never_drawn = HashSet::new();
+
+        for &(line, _, numbers) in chunk.iter() {
+            for &number in numbers.iter() {
+                match seen.get(&number) {
+                    Some(&of) => {
+                        diagnostics.push(Diagnostic { line: *line, problem: Problem::DuplicateCell { number, of } });
+                    },
+                    None => {
+                        seen.insert(number, *line);
+                    },
+                }
+
+                if !first_draw.contains_key(&number) && never_drawn.insert(number) {
+                    diagnostics.push(Diagnostic { line: *line, problem: Problem::NeverDrawn { number } });
+                }
+            }
+        }
+
+        let numbers: Vec<Vec<i32>> = chunk.iter().map(|(_, _, numbers)| numbers.clone()).collect();
+        boards.push(Board::new(&numbers));
+    }
+
+    for b in game::play(&draws, &boards, &patterns).never {
+        let line = rows[b * height].0;
+        diagnostics.push(Diagnostic { line, problem: Problem::CannotWin { board: b + 1 } });
+    }
+
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}